@@ -0,0 +1,1334 @@
+use std::{cmp::{max, min}, collections::VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rustc_hash::FxHashMap;
+
+use crate::{events::*, models::*, names::NameLookup, ttk::estimate_time_to_kill, utils::{dps, percentage}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveStatusEffect {
+    pub source_id: EntityId,
+    pub status_effect_id: StatusEffectId,
+    pub applied_on: DateTime<Utc>,
+    /// Remaining shield, 0 when not a shield or depleted.
+    pub shield_hp: i64
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregatorOptions {
+    pub dps_bucket_size: TimeDelta,
+    pub rolling_dps_window: TimeDelta,
+    pub boss_hp_sample_interval: TimeDelta,
+    /// Boss hp history the time to kill is projected from.
+    pub time_to_kill_window: TimeDelta,
+    pub tracked_status_effects: Vec<TrackedStatusEffect>
+}
+
+impl Default for AggregatorOptions {
+    fn default() -> Self {
+        Self {
+            dps_bucket_size: TimeDelta::seconds(5),
+            rolling_dps_window: TimeDelta::seconds(10),
+            boss_hp_sample_interval: TimeDelta::seconds(1),
+            time_to_kill_window: TimeDelta::seconds(30),
+            tracked_status_effects: vec![]
+        }
+    }
+}
+
+/// Sum of damage within a sliding time window, kept up to date as hits come in and expire.
+#[derive(Debug, Default)]
+struct RollingWindow {
+    hits: VecDeque<(DateTime<Utc>, i64)>,
+    total_damage: i64
+}
+
+impl RollingWindow {
+    fn add(&mut self, recorded_on: DateTime<Utc>, damage: i64) {
+        self.hits.push_back((recorded_on, damage));
+        self.total_damage += damage;
+    }
+
+    fn expire(&mut self, window_started_on: DateTime<Utc>) {
+        while let Some((recorded_on, damage)) = self.hits.front().copied() {
+            if recorded_on > window_started_on {
+                break;
+            }
+
+            self.hits.pop_front();
+            self.total_damage -= damage;
+        }
+    }
+}
+
+/// Time a tracked status effect has been active on one target, from any source.
+#[derive(Debug, Default)]
+struct UptimeTracker {
+    active_since: Option<DateTime<Utc>>,
+    total: TimeDelta
+}
+
+impl UptimeTracker {
+    fn uptime(&self, now: DateTime<Utc>) -> TimeDelta {
+        self.total + self.active_since.map(|active_since| now - active_since).unwrap_or_default()
+    }
+}
+
+/// Maintains encounter, player, esther and boss stats from a stream of [`EncounterEvent`].
+#[derive(Debug)]
+pub struct Aggregator {
+    options: AggregatorOptions,
+    players: FxHashMap<EntityId, Player>,
+    esthers: Vec<Esther>,
+    boss: Option<Boss>,
+    status_effects: FxHashMap<EntityId, Vec<ActiveStatusEffect>>,
+    started_on: Option<DateTime<Utc>>,
+    updated_on: Option<DateTime<Utc>>,
+    stats: EncounterStats,
+    dps_timeline: DpsTimeline,
+    party_rolling_window: RollingWindow,
+    player_rolling_windows: FxHashMap<EntityId, RollingWindow>,
+    boss_hp_timeline: BossHpTimeline,
+    boss_hp_sampled_on: Option<DateTime<Utc>>,
+    uptime_trackers: FxHashMap<(EntityId, StatusEffectId), UptimeTracker>,
+    uptime: UptimeStats
+}
+
+impl Aggregator {
+    pub fn new() -> Self {
+        Self::with_options(AggregatorOptions::default())
+    }
+
+    pub fn with_options(options: AggregatorOptions) -> Self {
+        let dps_timeline = DpsTimeline {
+            bucket_size_seconds: options.dps_bucket_size.num_seconds().max(1),
+            ..Default::default()
+        };
+
+        Self {
+            options,
+            players: FxHashMap::default(),
+            esthers: vec![],
+            boss: None,
+            status_effects: FxHashMap::default(),
+            started_on: None,
+            updated_on: None,
+            stats: EncounterStats::default(),
+            dps_timeline,
+            party_rolling_window: RollingWindow::default(),
+            player_rolling_windows: FxHashMap::default(),
+            boss_hp_timeline: BossHpTimeline::default(),
+            boss_hp_sampled_on: None,
+            uptime_trackers: FxHashMap::default(),
+            uptime: UptimeStats::default()
+        }
+    }
+
+    pub fn options(&self) -> &AggregatorOptions {
+        &self.options
+    }
+
+    /// Starts reporting the uptime of `status_effect`, takes effect for status effects applied from now on.
+    pub fn track_status_effect(&mut self, status_effect: TrackedStatusEffect) {
+        self.options.tracked_status_effects.push(status_effect);
+    }
+
+    pub fn add_player(&mut self, player: Player) {
+        self.dps_timeline.player_damage.insert(player.id, vec![0; self.dps_timeline.party_damage.len()]);
+        self.players.insert(player.id, player);
+    }
+
+    pub fn add_esther(&mut self, esther: Esther) {
+        self.esthers.push(esther);
+    }
+
+    pub fn set_boss(&mut self, boss: Boss) {
+        self.boss = Some(boss);
+    }
+
+    /// Replaces the class, skill, boss and status effect names captured so far with the ones of `names`,
+    /// keeping those `names` does not know. The uptime names follow with the next [`Aggregator::update`].
+    pub fn refresh_names(&mut self, names: &impl NameLookup) {
+        if let Some(boss) = self.boss.as_mut() {
+            if let Some(name) = names.npc_name(boss.npc_id) {
+                boss.name = name;
+            }
+        }
+
+        let boss_name = self.boss.as_ref().map(|boss| boss.name.clone()).unwrap_or_default();
+
+        for player in self.players.values_mut() {
+            if let Some(class_name) = names.class_name(player.class_id) {
+                player.class_name = class_name;
+            }
+
+            for skill_stats in player.stats.skills.skill.values_mut() {
+                if let Some(skill) = names.skill(skill_stats.id) {
+                    skill_stats.name = skill.name;
+                    skill_stats.icon = skill.icon;
+                }
+            }
+
+            for skill_stats in player.stats.damage_taken_by_skill.values_mut() {
+                if let Some(skill) = names.skill(skill_stats.id) {
+                    skill_stats.name = skill.name;
+                }
+
+                if !skill_stats.source_name.is_empty() {
+                    skill_stats.source_name = boss_name.clone();
+                }
+            }
+        }
+
+        for status_effect in self.options.tracked_status_effects.iter_mut() {
+            if let Some(name) = names.status_effect_name(status_effect.id) {
+                status_effect.name = name;
+            }
+        }
+    }
+
+    pub fn player(&self, id: EntityId) -> Option<&Player> {
+        self.players.get(&id)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.players.values()
+    }
+
+    pub fn esthers(&self) -> &[Esther] {
+        &self.esthers
+    }
+
+    pub fn boss(&self) -> Option<&Boss> {
+        self.boss.as_ref()
+    }
+
+    pub fn stats(&self) -> &EncounterStats {
+        &self.stats
+    }
+
+    pub fn started_on(&self) -> Option<DateTime<Utc>> {
+        self.started_on
+    }
+
+    pub fn dps_timeline(&self) -> &DpsTimeline {
+        &self.dps_timeline
+    }
+
+    pub fn boss_hp_timeline(&self) -> &BossHpTimeline {
+        &self.boss_hp_timeline
+    }
+
+    pub fn active_status_effects(&self, target_id: EntityId) -> &[ActiveStatusEffect] {
+        self.status_effects
+            .get(&target_id)
+            .map(|status_effects| status_effects.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Remaining shield on `target_id` summed over all of its active shields.
+    pub fn shield_hp(&self, target_id: EntityId) -> i64 {
+        self.active_status_effects(target_id)
+            .iter()
+            .map(|status_effect| status_effect.shield_hp)
+            .sum()
+    }
+
+    pub fn process(&mut self, event: EncounterEvent) {
+        self.started_on.get_or_insert(event.recorded_on());
+
+        match event {
+            EncounterEvent::SkillCast(event) => self.on_skill_cast(event),
+            EncounterEvent::Damage(event) => self.on_damage(event),
+            EncounterEvent::Heal(event) => self.on_heal(event),
+            EncounterEvent::Death(event) => self.on_death(event),
+            EncounterEvent::Resurrect(event) => self.on_resurrect(event),
+            EncounterEvent::StatusEffect(event) => self.on_status_effect(event),
+            EncounterEvent::Esther(event) => self.on_esther(event),
+            EncounterEvent::BossShield(event) => self.on_boss_shield(event),
+            EncounterEvent::PhaseTransition(event) => self.on_phase_transition(event),
+        }
+    }
+
+    /// Recalculates time dependent and relative stats: duration, dps, time to kill and percentages.
+    pub fn update(&mut self, now: DateTime<Utc>) {
+        let started_on = *self.started_on.get_or_insert(now);
+        let duration_ms = (now - started_on).num_milliseconds();
+        self.updated_on = Some(now);
+        self.resize_dps_timeline(now);
+
+        let should_sample_boss_hp = self.boss_hp_sampled_on
+            .is_none_or(|sampled_on| now - sampled_on >= self.options.boss_hp_sample_interval);
+
+        if should_sample_boss_hp {
+            self.sample_boss_hp(now);
+        }
+
+        let window = self.options.rolling_dps_window;
+        let window_ms = min(window.num_milliseconds(), duration_ms);
+        let window_started_on = now - window;
+        let stats = &mut self.stats;
+
+        stats.duration = now - started_on;
+        stats.party_dps = dps(stats.total_player_damage, duration_ms);
+        self.party_rolling_window.expire(window_started_on);
+        stats.party_rolling_dps = dps(self.party_rolling_window.total_damage, window_ms);
+
+        stats.time_to_kill = estimate_time_to_kill(&self.boss_hp_timeline.samples, self.options.time_to_kill_window);
+
+        for player in self.players.values_mut() {
+            let death_log = &mut player.death_log;
+
+            if death_log.is_dead {
+                death_log.duration = death_log.recorded_on.map(|recorded_on| now - recorded_on);
+            }
+
+            death_log.total_dead_ms = death_log.windows
+                .iter()
+                .map(|window| (window.resurrected_on.unwrap_or(now) - max(window.died_on, started_on)).num_milliseconds())
+                .map(|dead_ms| max(dead_ms, 0))
+                .sum();
+            let alive_ms = max(duration_ms - death_log.total_dead_ms, 0);
+
+            let player_stats = &mut player.stats;
+            player_stats.dps = dps(player_stats.total_damage, duration_ms);
+            player_stats.alive_dps = dps(player_stats.total_damage, alive_ms);
+
+            for skill_stat in player_stats.skills.skill.values_mut() {
+                skill_stat.dps = dps(skill_stat.total_damage, duration_ms);
+                skill_stat.damage_percentage = percentage(skill_stat.total_damage, player_stats.total_damage);
+            }
+
+            if let Some(rolling_window) = self.player_rolling_windows.get_mut(&player.id) {
+                rolling_window.expire(window_started_on);
+                player_stats.rolling_dps = dps(rolling_window.total_damage, window_ms);
+            }
+            player_stats.damage_percentage = percentage(player_stats.total_damage, stats.total_player_damage);
+            player_stats.damage_taken_percentage = percentage(player_stats.damage_taken, stats.total_taken_damage);
+            player_stats.stagger_percentage = percentage(player_stats.stagger, stats.total_stagger);
+            player_stats.destruction_percentage = percentage(player_stats.destruction, stats.total_destruction);
+        }
+
+        self.update_uptime(now - started_on, now);
+    }
+
+    pub fn to_fight_update(&self) -> Option<FightUpdate> {
+        let boss = self.boss.clone()?;
+        let mut players: Vec<Player> = self.players.values().cloned().collect();
+        players.sort_unstable_by_key(|player| player.stats.total_damage);
+
+        let fight_update = FightUpdate {
+            stats: self.stats.clone(),
+            esthers: self.esthers.clone(),
+            players,
+            boss,
+            dps_timeline: self.dps_timeline.clone(),
+            boss_hp_timeline: CompactBossHpTimeline {
+                hp_bar_changes: self.boss_hp_timeline.hp_bar_changes.clone(),
+                phases: self.boss_hp_timeline.phases.clone()
+            },
+            uptime: self.uptime.clone()
+        };
+
+        Some(fight_update)
+    }
+
+    pub fn to_encounter(&self) -> Option<Encounter> {
+        let FightUpdate { stats, esthers, players, boss, dps_timeline, uptime, .. } = self.to_fight_update()?;
+
+        let encounter = Encounter {
+            started_on: self.started_on?,
+            ended_on: self.updated_on?,
+            stats,
+            esthers,
+            players,
+            boss,
+            dps_timeline,
+            boss_hp_timeline: self.boss_hp_timeline.clone(),
+            uptime
+        };
+
+        Some(encounter)
+    }
+
+    fn on_skill_cast(&mut self, event: SkillCastEvent) {
+        let Some(player) = self.players.get_mut(&event.source_id) else {
+            return;
+        };
+
+        Self::player_skill_stats(player, &event.skill).cast_count += 1;
+    }
+
+    fn player_skill_stats<'p>(player: &'p mut Player, skill: &Skill) -> &'p mut PlayerSkillStats {
+        player.stats.skills.skill.entry(skill.id).or_insert_with(|| PlayerSkillStats {
+            id: skill.id,
+            name: skill.name.clone(),
+            icon: skill.icon.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn on_damage(&mut self, event: DamageEvent) {
+        if let Some(player) = self.players.get_mut(&event.source_id) {
+            self.stats.total_player_damage += event.damage;
+            self.stats.total_stagger += event.stagger;
+            self.stats.total_destruction += event.destruction;
+            self.stats.top_damage = max(event.damage, self.stats.top_damage);
+            Self::update_player(player, &event, self.stats.total_player_damage);
+
+            let bucket = self.resize_dps_timeline(event.recorded_on);
+            self.dps_timeline.party_damage[bucket] += event.damage;
+
+            if let Some(player_damage) = self.dps_timeline.player_damage.get_mut(&event.source_id) {
+                player_damage[bucket] += event.damage;
+            }
+
+            let window_started_on = event.recorded_on - self.options.rolling_dps_window;
+            self.party_rolling_window.add(event.recorded_on, event.damage);
+            self.party_rolling_window.expire(window_started_on);
+            let rolling_window = self.player_rolling_windows.entry(event.source_id).or_default();
+            rolling_window.add(event.recorded_on, event.damage);
+            rolling_window.expire(window_started_on);
+
+            let offset_ms = self.offset_ms(event.recorded_on);
+
+            if let Some(boss) = self.boss.as_mut().filter(|boss| boss.id == event.target_id) {
+                let hp_bars = boss.stats.hp_bars;
+                Self::update_boss(&mut boss.stats, &event);
+
+                if boss.stats.hp_bars != hp_bars {
+                    let hp_bar_change = HpBarChange {
+                        offset_ms,
+                        hp_bars: boss.stats.hp_bars
+                    };
+                    self.boss_hp_timeline.hp_bar_changes.push(hp_bar_change);
+                }
+            }
+
+            return;
+        }
+
+        if let Some(player) = self.players.get_mut(&event.target_id) {
+            self.stats.total_taken_damage += event.damage;
+            let stats = &mut player.stats;
+            stats.damage_taken += event.damage;
+            stats.damage_taken_percentage = percentage(stats.damage_taken, self.stats.total_taken_damage);
+
+            let boss = self.boss.as_mut().filter(|boss| boss.id == event.source_id);
+            let source_name = boss.as_ref().map(|boss| boss.name.clone()).unwrap_or_default();
+
+            if let Some(boss) = boss {
+                boss.stats.total_damage_dealt += event.damage;
+            }
+
+            let skill_stat = stats.damage_taken_by_skill.entry(event.skill.id).or_insert_with(|| {
+                DamageTakenSkillStats {
+                    id: event.skill.id,
+                    name: event.skill.name.clone(),
+                    source_name,
+                    ..Default::default()
+                }
+            });
+
+            skill_stat.hit_count += 1;
+            skill_stat.total_damage += event.damage;
+
+            if event.shield_damage > 0 {
+                skill_stat.shielded_hit_count += 1;
+                skill_stat.shielded_damage += event.shield_damage;
+                stats.damage_absorbed_received += event.shield_damage;
+                self.absorb_shield_damage(event.target_id, event.shield_damage);
+            }
+        }
+    }
+
+    /// Depletes the oldest shields on `target_id` first and credits their sources.
+    fn absorb_shield_damage(&mut self, target_id: EntityId, shield_damage: i64) {
+        let Some(status_effects) = self.status_effects.get_mut(&target_id) else {
+            return;
+        };
+
+        let mut remaining = shield_damage;
+
+        for status_effect in status_effects.iter_mut().filter(|status_effect| status_effect.shield_hp > 0) {
+            if remaining == 0 {
+                break;
+            }
+
+            let absorbed = min(status_effect.shield_hp, remaining);
+            status_effect.shield_hp -= absorbed;
+            remaining -= absorbed;
+
+            if let Some(source) = self.players.get_mut(&status_effect.source_id) {
+                source.stats.damage_absorbed_given += absorbed;
+            }
+        }
+    }
+
+    fn on_heal(&mut self, event: HealEvent) {
+        if let Some(player) = self.players.get_mut(&event.source_id) {
+            player.stats.healing_done += event.amount;
+        }
+
+        if let Some(player) = self.players.get_mut(&event.target_id) {
+            player.stats.healing_received += event.amount;
+        }
+    }
+
+    fn on_death(&mut self, event: DeathEvent) {
+        let Some(player) = self.players.get_mut(&event.target_id) else {
+            return;
+        };
+
+        let death_log = &mut player.death_log;
+
+        if death_log.is_dead {
+            return;
+        }
+
+        death_log.is_dead = true;
+        death_log.death_count += 1;
+        death_log.recorded_on = Some(event.recorded_on);
+        death_log.duration = Some(TimeDelta::zero());
+        death_log.windows.push(DeathWindow {
+            died_on: event.recorded_on,
+            resurrected_on: None
+        });
+
+        self.stats.death_timeline.push(DeathTimelineEntry {
+            player_id: event.target_id,
+            kind: DeathTimelineKind::Death,
+            recorded_on: event.recorded_on
+        });
+    }
+
+    fn on_resurrect(&mut self, event: ResurrectEvent) {
+        let Some(player) = self.players.get_mut(&event.target_id) else {
+            return;
+        };
+
+        let death_log = &mut player.death_log;
+
+        if !death_log.is_dead {
+            return;
+        }
+
+        death_log.is_dead = false;
+        death_log.duration = None;
+
+        if let Some(window) = death_log.windows.last_mut() {
+            window.resurrected_on = Some(event.recorded_on);
+        }
+
+        self.stats.death_timeline.push(DeathTimelineEntry {
+            player_id: event.target_id,
+            kind: DeathTimelineKind::Resurrect,
+            recorded_on: event.recorded_on
+        });
+    }
+
+    fn on_status_effect(&mut self, event: StatusEffectEvent) {
+        let status_effects = self.status_effects.entry(event.target_id).or_default();
+        status_effects.retain(|status_effect| status_effect.status_effect_id != event.status_effect_id
+            || status_effect.source_id != event.source_id);
+
+        if event.kind == StatusEffectEventKind::Applied {
+            status_effects.push(ActiveStatusEffect {
+                source_id: event.source_id,
+                status_effect_id: event.status_effect_id,
+                applied_on: event.recorded_on,
+                shield_hp: event.shield_hp
+            });
+        }
+
+        self.track_uptime(event.target_id, event.status_effect_id, event.recorded_on);
+
+        if event.kind != StatusEffectEventKind::Applied || event.shield_hp == 0 {
+            return;
+        }
+
+        if let Some(source) = self.players.get_mut(&event.source_id) {
+            source.stats.shield_given += event.shield_hp;
+        }
+
+        if let Some(target) = self.players.get_mut(&event.target_id) {
+            target.stats.shield_received += event.shield_hp;
+        }
+    }
+
+    /// Starts or stops the uptime of a tracked status effect when the first source applies it
+    /// or the last one removes it.
+    fn track_uptime(&mut self, target_id: EntityId, status_effect_id: StatusEffectId, recorded_on: DateTime<Utc>) {
+        if !self.options.tracked_status_effects.iter().any(|status_effect| status_effect.id == status_effect_id) {
+            return;
+        }
+
+        let is_active = self.active_status_effects(target_id)
+            .iter()
+            .any(|status_effect| status_effect.status_effect_id == status_effect_id);
+        let tracker = self.uptime_trackers.entry((target_id, status_effect_id)).or_default();
+
+        match tracker.active_since {
+            None if is_active => tracker.active_since = Some(recorded_on),
+            Some(active_since) if !is_active => {
+                tracker.total += recorded_on - active_since;
+                tracker.active_since = None;
+            },
+            _ => {}
+        }
+    }
+
+    fn update_uptime(&mut self, duration: TimeDelta, now: DateTime<Utc>) {
+        let duration_ms = duration.num_milliseconds();
+        let uptime = |target_id: EntityId| -> Vec<StatusEffectUptime> {
+            self.options.tracked_status_effects.iter().map(|status_effect| {
+                let uptime_ms = self.uptime_trackers
+                    .get(&(target_id, status_effect.id))
+                    .map(|tracker| tracker.uptime(now).num_milliseconds())
+                    .unwrap_or_default();
+
+                StatusEffectUptime {
+                    id: status_effect.id,
+                    name: status_effect.name.clone(),
+                    uptime_ms,
+                    percentage: percentage(uptime_ms, duration_ms)
+                }
+            })
+            .collect()
+        };
+
+        let boss = self.boss.as_ref().map(|boss| uptime(boss.id)).unwrap_or_default();
+        let players = self.players.keys().map(|id| (*id, uptime(*id))).collect();
+
+        self.uptime = UptimeStats {
+            boss,
+            players
+        };
+    }
+
+    fn on_esther(&mut self, event: EstherEvent) {
+        let Some(esther) = self.esthers.iter_mut().find(|esther| esther.id == event.esther_id) else {
+            return;
+        };
+
+        esther.stats.total_damage += event.damage;
+        self.stats.total_esther_damage += event.damage;
+
+        for esther in self.esthers.iter_mut() {
+            let stats = &mut esther.stats;
+            stats.damage_percentage = percentage(stats.total_damage, self.stats.total_esther_damage);
+        }
+
+        self.stats.esther_usage_log.push(EstherUsageLog {
+            esther_id: event.esther_id,
+            player_id: event.source_id,
+            damage: event.damage,
+            recorded_on: event.recorded_on
+        });
+    }
+
+    fn on_boss_shield(&mut self, event: BossShieldEvent) {
+        if let Some(boss) = self.boss.as_mut() {
+            boss.stats.shield_hp = event.shield_hp;
+            boss.stats.updated_on = event.recorded_on;
+        }
+
+        self.sample_boss_hp(event.recorded_on);
+    }
+
+    fn on_phase_transition(&mut self, event: PhaseTransitionEvent) {
+        let phase_marker = PhaseMarker {
+            offset_ms: self.offset_ms(event.recorded_on),
+            phase: event.phase
+        };
+        self.boss_hp_timeline.phases.push(phase_marker);
+        self.sample_boss_hp(event.recorded_on);
+    }
+
+    fn sample_boss_hp(&mut self, now: DateTime<Utc>) {
+        let Some(boss) = self.boss.as_ref() else {
+            return;
+        };
+
+        let sample = BossHpSample {
+            offset_ms: self.offset_ms(now),
+            hp: boss.stats.hp,
+            hp_bars: boss.stats.hp_bars,
+            shield_hp: boss.stats.shield_hp
+        };
+
+        self.boss_hp_timeline.samples.push(sample);
+        self.boss_hp_sampled_on = Some(now);
+    }
+
+    fn offset_ms(&self, now: DateTime<Utc>) -> i64 {
+        self.started_on.map_or(0, |started_on| (now - started_on).num_milliseconds())
+    }
+
+    /// Makes sure the timeline has a bucket for given time and returns its index.
+    fn resize_dps_timeline(&mut self, now: DateTime<Utc>) -> usize {
+        let started_on = self.started_on.unwrap_or(now);
+        let elapsed_ms = max((now - started_on).num_milliseconds(), 0);
+        let bucket_size_ms = self.dps_timeline.bucket_size_seconds * 1000;
+        let bucket = (elapsed_ms / bucket_size_ms) as usize;
+        let timeline = &mut self.dps_timeline;
+
+        if timeline.party_damage.len() <= bucket {
+            timeline.party_damage.resize(bucket + 1, 0);
+
+            for player_damage in timeline.player_damage.values_mut() {
+                player_damage.resize(bucket + 1, 0);
+            }
+        }
+
+        bucket
+    }
+
+    fn update_player(player: &mut Player, event: &DamageEvent, total_damage: i64) {
+        let damage = event.damage;
+        let stats = &mut player.stats;
+
+        stats.top_damage = max(damage, stats.top_damage);
+        stats.total_damage += damage;
+
+        if event.is_hyper_awakening {
+            stats.hyper_awakening_damage += damage;
+        }
+
+        stats.skills.hit_count += 1;
+        stats.stagger += event.stagger;
+        stats.destruction += event.destruction;
+
+        if event.is_counter {
+            stats.skills.counter_count += 1;
+        }
+
+        let is_critical = event.hit_flag == HitFlag::Critical;
+
+        if is_critical {
+            stats.crit_damage += damage;
+            stats.skills.crit_count += 1;
+        }
+
+        match event.hit_option {
+            HitOption::BackAttack => stats.back_attacks_total_damage += damage,
+            HitOption::FrontalAttack => stats.front_attacks_total_damage += damage,
+            HitOption::None | HitOption::FlankAttack | HitOption::Max => stats.non_positional_attacks_total_damage += damage,
+        }
+
+        let skill_stat = Self::player_skill_stats(player, &event.skill);
+
+        if is_critical {
+            skill_stat.crit_count += 1;
+            skill_stat.crit_damage += damage;
+        }
+
+        match event.hit_option {
+            HitOption::BackAttack => skill_stat.back_attack_count += 1,
+            HitOption::FrontalAttack => skill_stat.front_attack_count += 1,
+            _ => {}
+        }
+
+        skill_stat.total_damage += damage;
+        skill_stat.hit_count += 1;
+        skill_stat.max_hit = max(damage, skill_stat.max_hit);
+        skill_stat.average_hit = skill_stat.total_damage / skill_stat.hit_count as i64;
+        skill_stat.crit_damage_percentage = percentage(skill_stat.crit_damage, skill_stat.total_damage);
+        skill_stat.back_attack_rate = skill_stat.back_attack_count as f32 / skill_stat.hit_count as f32;
+        skill_stat.front_attack_rate = skill_stat.front_attack_count as f32 / skill_stat.hit_count as f32;
+
+        let stats = &mut player.stats;
+
+        stats.crit_rate = stats.skills.crit_count as f32 / stats.skills.hit_count as f32;
+        stats.damage_percentage = percentage(stats.total_damage, total_damage);
+        stats.front_attacks_damage_percentage = percentage(stats.front_attacks_total_damage, stats.total_damage);
+        stats.back_attacks_damage_percentage = percentage(stats.back_attacks_total_damage, stats.total_damage);
+        stats.non_positional_attacks_damage_percentage = percentage(stats.non_positional_attacks_total_damage, stats.total_damage);
+        stats.updated_on = event.recorded_on;
+    }
+
+    fn update_boss(stats: &mut BossStats, event: &DamageEvent) {
+        stats.shield_hp = max(stats.shield_hp - event.shield_damage, 0);
+        stats.hp = max(stats.hp - (event.damage - event.shield_damage), 0);
+        stats.hp_percentage = (stats.hp as f32) / (stats.max_hp as f32) * 100.0;
+        stats.hp_bars = (stats.hp as f32 / stats.hp_per_bar).floor() as i64;
+        stats.damage_taken += event.damage;
+        stats.shield_damage_taken += event.shield_damage;
+        stats.updated_on = event.recorded_on;
+    }
+}
+
+impl Default for Aggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::*;
+
+    use super::*;
+
+    const BOSS_ID: EntityId = 1;
+    const PLAYER_ID: EntityId = 2;
+    const SUPPORT_ID: EntityId = 3;
+    const ESTHER_ID: EntityId = 4;
+
+    fn damage(source_id: EntityId, target_id: EntityId, damage: i64, hit_flag: HitFlag, recorded_on: DateTime<Utc>) -> EncounterEvent {
+        EncounterEvent::Damage(DamageEvent {
+            source_id,
+            target_id,
+            skill: skill(),
+            damage,
+            hit_flag,
+            hit_option: HitOption::BackAttack,
+            is_hyper_awakening: false,
+            is_counter: false,
+            stagger: 0,
+            destruction: 0,
+            shield_damage: 0,
+            recorded_on
+        })
+    }
+
+    /// Hit of the player on the boss of which the boss shield absorbed `shield_damage`.
+    fn shielded_damage(damage: i64, shield_damage: i64, recorded_on: DateTime<Utc>) -> EncounterEvent {
+        let EncounterEvent::Damage(event) = self::damage(PLAYER_ID, BOSS_ID, damage, HitFlag::Normal, recorded_on) else {
+            unreachable!()
+        };
+
+        EncounterEvent::Damage(DamageEvent {
+            shield_damage,
+            ..event
+        })
+    }
+
+    fn aggregator(now: DateTime<Utc>) -> Aggregator {
+        let mut aggregator = Aggregator::new();
+        aggregator.add_player(player(PLAYER_ID, now));
+        aggregator.add_player(player(SUPPORT_ID, now));
+        aggregator.add_esther(Esther {
+            id: ESTHER_ID,
+            name: "Azena".into(),
+            icon: "azena.png".into(),
+            stats: EstherStats::default()
+        });
+        aggregator.set_boss(boss(BOSS_ID, 10, 100, now));
+
+        aggregator
+    }
+
+    #[test]
+    fn test_damage_dealt_updates_player_and_boss() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 150, HitFlag::Critical, now));
+
+        let stats = &aggregator.player(PLAYER_ID).unwrap().stats;
+        assert_eq!(stats.total_damage, 250);
+        assert_eq!(stats.top_damage, 150);
+        assert_eq!(stats.crit_damage, 150);
+        assert_eq!(stats.crit_rate, 0.5);
+        assert_eq!(stats.back_attacks_damage_percentage, 1.0);
+        assert_eq!(stats.skills.skill[&16010].hit_count, 2);
+
+        let boss_stats = &aggregator.boss().unwrap().stats;
+        assert_eq!(boss_stats.hp, 750);
+        assert_eq!(boss_stats.hp_bars, 7);
+        assert_eq!(aggregator.stats().total_player_damage, 250);
+    }
+
+    #[test]
+    fn test_damage_on_boss_does_not_go_below_zero() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 5000, HitFlag::Normal, now));
+
+        assert_eq!(aggregator.boss().unwrap().stats.hp, 0);
+    }
+
+    #[test]
+    fn test_damage_taken_updates_player() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(BOSS_ID, PLAYER_ID, 300, HitFlag::Normal, now));
+        aggregator.process(damage(BOSS_ID, SUPPORT_ID, 100, HitFlag::Normal, now));
+        aggregator.update(now);
+
+        let stats = &aggregator.player(PLAYER_ID).unwrap().stats;
+        assert_eq!(stats.damage_taken, 300);
+        assert_eq!(stats.damage_taken_percentage, 0.75);
+        assert_eq!(stats.total_damage, 0);
+        assert_eq!(aggregator.stats().total_taken_damage, 400);
+        assert_eq!(aggregator.boss().unwrap().stats.total_damage_dealt, 400);
+    }
+
+    #[test]
+    fn test_skill_stats_track_casts_hits_and_shares() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        let cast = EncounterEvent::SkillCast(SkillCastEvent {
+            source_id: PLAYER_ID,
+            skill: skill(),
+            recorded_on: now
+        });
+
+        aggregator.process(cast.clone());
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(cast);
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 300, HitFlag::Critical, now));
+        aggregator.update(now + TimeDelta::seconds(4));
+
+        let skill_stat = &aggregator.player(PLAYER_ID).unwrap().stats.skills.skill[&16010];
+        assert_eq!(skill_stat.cast_count, 2);
+        assert_eq!(skill_stat.hit_count, 2);
+        assert_eq!(skill_stat.max_hit, 300);
+        assert_eq!(skill_stat.average_hit, 200);
+        assert_eq!(skill_stat.dps, 100);
+        assert_eq!(skill_stat.damage_percentage, 1.0);
+        assert_eq!(skill_stat.crit_damage_percentage, 0.75);
+        assert_eq!(skill_stat.back_attack_rate, 1.0);
+        assert_eq!(skill_stat.front_attack_rate, 0.0);
+    }
+
+    #[test]
+    fn test_counters_stagger_and_destruction() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        let hit = |source_id, is_counter, stagger, destruction| EncounterEvent::Damage(DamageEvent {
+            source_id,
+            target_id: BOSS_ID,
+            skill: skill(),
+            damage: 10,
+            shield_damage: 0,
+            hit_flag: HitFlag::Normal,
+            hit_option: HitOption::None,
+            is_hyper_awakening: false,
+            is_counter,
+            stagger,
+            destruction,
+            recorded_on: now
+        });
+
+        aggregator.process(hit(PLAYER_ID, true, 300, 1));
+        aggregator.process(hit(PLAYER_ID, false, 100, 0));
+        aggregator.process(hit(SUPPORT_ID, false, 400, 1));
+        aggregator.update(now);
+
+        let stats = &aggregator.player(PLAYER_ID).unwrap().stats;
+        assert_eq!(stats.skills.counter_count, 1);
+        assert_eq!(stats.stagger, 400);
+        assert_eq!(stats.stagger_percentage, 0.5);
+        assert_eq!(stats.destruction, 1);
+        assert_eq!(stats.destruction_percentage, 0.5);
+        assert_eq!(aggregator.stats().total_stagger, 800);
+        assert_eq!(aggregator.stats().total_destruction, 2);
+    }
+
+    #[test]
+    fn test_damage_taken_is_tracked_per_incoming_skill() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        let shielded_hit = EncounterEvent::Damage(DamageEvent {
+            source_id: BOSS_ID,
+            target_id: PLAYER_ID,
+            skill: skill(),
+            damage: 50,
+            shield_damage: 250,
+            hit_flag: HitFlag::Normal,
+            hit_option: HitOption::None,
+            is_hyper_awakening: false,
+            is_counter: false,
+            stagger: 0,
+            destruction: 0,
+            recorded_on: now
+        });
+
+        aggregator.process(damage(BOSS_ID, PLAYER_ID, 300, HitFlag::Normal, now));
+        aggregator.process(shielded_hit);
+
+        let skill_stat = &aggregator.player(PLAYER_ID).unwrap().stats.damage_taken_by_skill[&16010];
+        assert_eq!(skill_stat.source_name, "Red Doom Narkiel");
+        assert_eq!(skill_stat.hit_count, 2);
+        assert_eq!(skill_stat.total_damage, 350);
+        assert_eq!(skill_stat.shielded_hit_count, 1);
+        assert_eq!(skill_stat.shielded_damage, 250);
+    }
+
+    #[test]
+    fn test_heal_updates_source_and_target() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(EncounterEvent::Heal(HealEvent {
+            source_id: SUPPORT_ID,
+            target_id: PLAYER_ID,
+            amount: 500,
+            recorded_on: now
+        }));
+
+        assert_eq!(aggregator.player(SUPPORT_ID).unwrap().stats.healing_done, 500);
+        assert_eq!(aggregator.player(PLAYER_ID).unwrap().stats.healing_received, 500);
+    }
+
+    #[test]
+    fn test_death_updates_death_log() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(EncounterEvent::Death(DeathEvent {
+            target_id: PLAYER_ID,
+            recorded_on: now
+        }));
+        aggregator.update(now + TimeDelta::seconds(5));
+
+        let death_log = &aggregator.player(PLAYER_ID).unwrap().death_log;
+        assert!(death_log.is_dead);
+        assert_eq!(death_log.death_count, 1);
+        assert_eq!(death_log.duration, Some(TimeDelta::seconds(5)));
+    }
+
+    #[test]
+    fn test_alive_dps_excludes_time_dead() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(EncounterEvent::Death(DeathEvent {
+            target_id: PLAYER_ID,
+            recorded_on: now + TimeDelta::seconds(2)
+        }));
+        aggregator.process(EncounterEvent::Resurrect(ResurrectEvent {
+            target_id: PLAYER_ID,
+            recorded_on: now + TimeDelta::seconds(4)
+        }));
+        aggregator.process(EncounterEvent::Death(DeathEvent {
+            target_id: PLAYER_ID,
+            recorded_on: now + TimeDelta::seconds(7)
+        }));
+        aggregator.update(now + TimeDelta::seconds(10));
+
+        let player = aggregator.player(PLAYER_ID).unwrap();
+        assert_eq!(player.death_log.death_count, 2);
+        assert_eq!(player.death_log.windows.len(), 2);
+        assert_eq!(player.death_log.total_dead_ms, 5000);
+        assert_eq!(player.stats.dps, 10);
+        assert_eq!(player.stats.alive_dps, 20);
+
+        let kinds: Vec<_> = aggregator.stats().death_timeline.iter().map(|entry| entry.kind).collect();
+        assert_eq!(kinds, vec![DeathTimelineKind::Death, DeathTimelineKind::Resurrect, DeathTimelineKind::Death]);
+    }
+
+    #[test]
+    fn test_status_effect_applied_and_removed() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        let status_effect = |kind| EncounterEvent::StatusEffect(StatusEffectEvent {
+            source_id: SUPPORT_ID,
+            target_id: BOSS_ID,
+            status_effect_id: 210230,
+            kind,
+            shield_hp: 0,
+            recorded_on: now
+        });
+
+        aggregator.process(status_effect(StatusEffectEventKind::Applied));
+        aggregator.process(status_effect(StatusEffectEventKind::Applied));
+
+        let active = aggregator.active_status_effects(BOSS_ID);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].status_effect_id, 210230);
+
+        aggregator.process(status_effect(StatusEffectEventKind::Removed));
+        assert!(aggregator.active_status_effects(BOSS_ID).is_empty());
+    }
+
+    #[test]
+    fn test_uptime_of_tracked_status_effects() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        aggregator.track_status_effect(TrackedStatusEffect {
+            id: 210230,
+            name: "Brand".into()
+        });
+        let status_effect = |source_id, kind, seconds| EncounterEvent::StatusEffect(StatusEffectEvent {
+            source_id,
+            target_id: BOSS_ID,
+            status_effect_id: 210230,
+            kind,
+            shield_hp: 0,
+            recorded_on: now + TimeDelta::seconds(seconds)
+        });
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 10, HitFlag::Normal, now));
+        aggregator.process(status_effect(SUPPORT_ID, StatusEffectEventKind::Applied, 1));
+        aggregator.process(status_effect(PLAYER_ID, StatusEffectEventKind::Applied, 2));
+        aggregator.process(status_effect(SUPPORT_ID, StatusEffectEventKind::Removed, 3));
+        aggregator.process(status_effect(PLAYER_ID, StatusEffectEventKind::Removed, 4));
+        aggregator.process(status_effect(SUPPORT_ID, StatusEffectEventKind::Applied, 8));
+        aggregator.update(now + TimeDelta::seconds(10));
+
+        let uptime = aggregator.to_fight_update().unwrap().uptime;
+        assert_eq!(uptime.boss.len(), 1);
+        assert_eq!(uptime.boss[0].uptime_ms, 5000);
+        assert!((uptime.boss[0].percentage - 0.5).abs() < f32::EPSILON);
+        assert_eq!(uptime.players[&PLAYER_ID][0].uptime_ms, 0);
+    }
+
+    #[test]
+    fn test_shields_track_given_received_and_absorbed() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+        let shield = |target_id| EncounterEvent::StatusEffect(StatusEffectEvent {
+            source_id: SUPPORT_ID,
+            target_id,
+            status_effect_id: 211601,
+            kind: StatusEffectEventKind::Applied,
+            shield_hp: 300,
+            recorded_on: now
+        });
+
+        aggregator.process(shield(PLAYER_ID));
+        aggregator.process(shield(SUPPORT_ID));
+        aggregator.process(EncounterEvent::Damage(DamageEvent {
+            source_id: BOSS_ID,
+            target_id: PLAYER_ID,
+            skill: skill(),
+            damage: 0,
+            shield_damage: 200,
+            hit_flag: HitFlag::Normal,
+            hit_option: HitOption::None,
+            is_hyper_awakening: false,
+            is_counter: false,
+            stagger: 0,
+            destruction: 0,
+            recorded_on: now
+        }));
+
+        let support = &aggregator.player(SUPPORT_ID).unwrap().stats;
+        assert_eq!(support.shield_given, 600);
+        assert_eq!(support.shield_received, 300);
+        assert_eq!(support.damage_absorbed_given, 200);
+        assert_eq!(support.damage_absorbed_received, 0);
+
+        let player = &aggregator.player(PLAYER_ID).unwrap().stats;
+        assert_eq!(player.shield_received, 300);
+        assert_eq!(player.damage_absorbed_received, 200);
+        assert_eq!(aggregator.shield_hp(PLAYER_ID), 100);
+    }
+
+    #[test]
+    fn test_esther_updates_stats_and_usage_log() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(EncounterEvent::Esther(EstherEvent {
+            source_id: PLAYER_ID,
+            esther_id: ESTHER_ID,
+            damage: 2000,
+            recorded_on: now
+        }));
+
+        let esther = &aggregator.esthers()[0];
+        assert_eq!(esther.stats.total_damage, 2000);
+        assert_eq!(esther.stats.damage_percentage, 1.0);
+        assert_eq!(aggregator.stats().total_esther_damage, 2000);
+        assert_eq!(aggregator.stats().esther_usage_log, vec![EstherUsageLog {
+            esther_id: ESTHER_ID,
+            player_id: PLAYER_ID,
+            damage: 2000,
+            recorded_on: now
+        }]);
+    }
+
+    #[test]
+    fn test_update_calculates_dps() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.update(now + TimeDelta::seconds(10));
+
+        assert_eq!(aggregator.player(PLAYER_ID).unwrap().stats.dps, 10);
+        assert_eq!(aggregator.stats().party_dps, 10);
+        assert_eq!(aggregator.stats().duration, TimeDelta::seconds(10));
+    }
+
+    #[test]
+    fn test_update_projects_time_to_kill_from_boss_hp_loss() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        for second in 1..=5 {
+            let recorded_on = now + TimeDelta::seconds(second);
+            aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, recorded_on));
+            aggregator.update(recorded_on);
+        }
+
+        let time_to_kill = aggregator.stats().time_to_kill.clone().unwrap();
+        assert_eq!(time_to_kill.hp_loss_per_second, 100);
+        assert_eq!(time_to_kill.estimate, TimeDelta::seconds(5));
+    }
+
+    #[test]
+    fn test_dps_is_calculated_within_the_first_second() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.update(now + TimeDelta::milliseconds(250));
+
+        assert_eq!(aggregator.player(PLAYER_ID).unwrap().stats.dps, 400);
+        assert_eq!(aggregator.stats().party_dps, 400);
+        assert_eq!(aggregator.stats().duration, TimeDelta::milliseconds(250));
+    }
+
+    #[test]
+    fn test_damage_is_recorded_in_dps_timeline_buckets() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 50, HitFlag::Normal, now + TimeDelta::seconds(4)));
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 200, HitFlag::Normal, now + TimeDelta::seconds(11)));
+        aggregator.update(now + TimeDelta::seconds(16));
+
+        let dps_timeline = aggregator.dps_timeline();
+        assert_eq!(dps_timeline.bucket_size_seconds, 5);
+        assert_eq!(dps_timeline.party_damage, vec![150, 0, 200, 0]);
+        assert_eq!(dps_timeline.player_damage[&PLAYER_ID], vec![150, 0, 200, 0]);
+        assert_eq!(dps_timeline.player_damage[&SUPPORT_ID], vec![0, 0, 0, 0]);
+        assert_eq!(dps_timeline.party_dps(), vec![30, 0, 40, 0]);
+    }
+
+    #[test]
+    fn test_boss_hp_timeline_records_samples_bars_and_phases() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 50, HitFlag::Normal, now));
+        aggregator.update(now);
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now + TimeDelta::milliseconds(500)));
+        aggregator.update(now + TimeDelta::milliseconds(500));
+        aggregator.process(EncounterEvent::PhaseTransition(PhaseTransitionEvent {
+            phase: 2,
+            recorded_on: now + TimeDelta::seconds(2)
+        }));
+        aggregator.process(EncounterEvent::BossShield(BossShieldEvent {
+            shield_hp: 80,
+            recorded_on: now + TimeDelta::seconds(2)
+        }));
+        aggregator.process(shielded_damage(100, 80, now + TimeDelta::seconds(3)));
+
+        let boss_stats = &aggregator.boss().unwrap().stats;
+        assert_eq!(boss_stats.shield_hp, 0);
+        assert_eq!(boss_stats.hp, 830);
+
+        let timeline = aggregator.boss_hp_timeline();
+        assert_eq!(timeline.hp_bar_changes, vec![
+            HpBarChange { offset_ms: 0, hp_bars: 9 },
+            HpBarChange { offset_ms: 500, hp_bars: 8 },
+        ]);
+        assert_eq!(timeline.phases, vec![PhaseMarker { offset_ms: 2000, phase: 2 }]);
+        assert_eq!(timeline.samples, vec![
+            BossHpSample { offset_ms: 0, hp: 950, hp_bars: 9, shield_hp: 0 },
+            BossHpSample { offset_ms: 2000, hp: 850, hp_bars: 8, shield_hp: 0 },
+            BossHpSample { offset_ms: 2000, hp: 850, hp_bars: 8, shield_hp: 80 },
+        ]);
+    }
+
+    #[test]
+    fn test_boss_shield_takes_the_reported_shield_damage() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(EncounterEvent::BossShield(BossShieldEvent {
+            shield_hp: 80,
+            recorded_on: now
+        }));
+        aggregator.process(shielded_damage(100, 30, now));
+
+        let boss_stats = &aggregator.boss().unwrap().stats;
+        assert_eq!(boss_stats.shield_hp, 50);
+        assert_eq!(boss_stats.hp, 930);
+        assert_eq!(boss_stats.damage_taken, 100);
+        assert_eq!(boss_stats.shield_damage_taken, 30);
+
+        aggregator.process(shielded_damage(100, 60, now));
+
+        let boss_stats = &aggregator.boss().unwrap().stats;
+        assert_eq!(boss_stats.shield_hp, 0);
+        assert_eq!(boss_stats.hp, 890);
+        assert_eq!(boss_stats.shield_damage_taken, 90);
+    }
+
+    #[test]
+    fn test_rolling_dps_only_counts_damage_within_window() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 200, HitFlag::Normal, now + TimeDelta::seconds(5)));
+        aggregator.update(now + TimeDelta::seconds(5));
+
+        assert_eq!(aggregator.player(PLAYER_ID).unwrap().stats.rolling_dps, 60);
+
+        aggregator.update(now + TimeDelta::seconds(12));
+
+        let stats = &aggregator.player(PLAYER_ID).unwrap().stats;
+        assert_eq!(stats.rolling_dps, 20);
+        assert_eq!(stats.dps, 25);
+        assert_eq!(aggregator.stats().party_rolling_dps, 20);
+    }
+    struct RenamedNames;
+
+    impl NameLookup for RenamedNames {
+        fn class_name(&self, _class_id: ClassId) -> Option<String> {
+            Some("Slayer".into())
+        }
+
+        fn skill(&self, skill_id: SkillId) -> Option<Skill> {
+            (skill_id == 16010).then(|| Skill {
+                id: skill_id,
+                name: "Red Dust II".into(),
+                icon: "red_dust_2.png".into()
+            })
+        }
+
+        fn npc_name(&self, _npc_id: NpcId) -> Option<String> {
+            Some("Narkiel".into())
+        }
+
+        fn status_effect_name(&self, _status_effect_id: StatusEffectId) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_refresh_names_replaces_known_names() {
+        let now = Utc::now();
+        let mut aggregator = aggregator(now);
+
+        aggregator.process(damage(PLAYER_ID, BOSS_ID, 100, HitFlag::Normal, now));
+        aggregator.process(damage(BOSS_ID, PLAYER_ID, 50, HitFlag::Normal, now));
+        aggregator.refresh_names(&RenamedNames);
+
+        assert_eq!(aggregator.boss().unwrap().name, "Narkiel");
+
+        let player = aggregator.player(PLAYER_ID).unwrap();
+        assert_eq!(player.class_name, "Slayer");
+
+        let skill_stats = &player.stats.skills.skill[&16010];
+        assert_eq!(skill_stats.name, "Red Dust II");
+        assert_eq!(skill_stats.icon, "red_dust_2.png");
+
+        let damage_taken = &player.stats.damage_taken_by_skill[&16010];
+        assert_eq!(damage_taken.name, "Red Dust II");
+        assert_eq!(damage_taken.source_name, "Narkiel");
+    }
+}
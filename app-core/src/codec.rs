@@ -0,0 +1,119 @@
+use std::{error::Error, fmt};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Wire format of [`crate::models::AppEvent`] payloads sent from the app to the web.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PayloadEncoding {
+    #[default]
+    Json,
+    /// MessagePack with structs as arrays, base64 encoded since tauri events are JSON.
+    MessagePack
+}
+
+/// Event payload as emitted, `data` holds the JSON document or the base64 MessagePack bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncodedPayload {
+    pub encoding: PayloadEncoding,
+    pub data: String
+}
+
+#[derive(Debug)]
+pub enum CodecError {
+    Json(serde_json::Error),
+    MessagePackEncode(rmp_serde::encode::Error),
+    MessagePackDecode(rmp_serde::decode::Error),
+    Base64(base64::DecodeError)
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Json(err) => write!(f, "json: {}", err),
+            CodecError::MessagePackEncode(err) => write!(f, "messagepack encode: {}", err),
+            CodecError::MessagePackDecode(err) => write!(f, "messagepack decode: {}", err),
+            CodecError::Base64(err) => write!(f, "base64: {}", err),
+        }
+    }
+}
+
+impl Error for CodecError {}
+
+/// Serializes `value` into the raw bytes of `encoding`, before any base64 wrapping.
+pub fn to_bytes<T: Serialize>(value: &T, encoding: PayloadEncoding) -> Result<Vec<u8>, CodecError> {
+    match encoding {
+        PayloadEncoding::Json => serde_json::to_vec(value).map_err(CodecError::Json),
+        PayloadEncoding::MessagePack => rmp_serde::to_vec(value).map_err(CodecError::MessagePackEncode),
+    }
+}
+
+/// Counterpart of [`to_bytes`].
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8], encoding: PayloadEncoding) -> Result<T, CodecError> {
+    match encoding {
+        PayloadEncoding::Json => serde_json::from_slice(bytes).map_err(CodecError::Json),
+        PayloadEncoding::MessagePack => rmp_serde::from_slice(bytes).map_err(CodecError::MessagePackDecode),
+    }
+}
+
+pub fn encode<T: Serialize>(value: &T, encoding: PayloadEncoding) -> Result<EncodedPayload, CodecError> {
+    let data = match encoding {
+        PayloadEncoding::Json => serde_json::to_string(value).map_err(CodecError::Json)?,
+        PayloadEncoding::MessagePack => STANDARD.encode(to_bytes(value, encoding)?),
+    };
+
+    Ok(EncodedPayload {
+        encoding,
+        data
+    })
+}
+
+pub fn decode<T: DeserializeOwned>(payload: &EncodedPayload) -> Result<T, CodecError> {
+    match payload.encoding {
+        PayloadEncoding::Json => serde_json::from_str(&payload.data).map_err(CodecError::Json),
+        PayloadEncoding::MessagePack => {
+            let bytes = STANDARD.decode(&payload.data).map_err(CodecError::Base64)?;
+            from_bytes(&bytes, payload.encoding)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::{fixtures, models::*};
+
+    use super::*;
+
+    #[test]
+    fn test_payload_roundtrip_in_every_encoding() {
+        let skill = DamageTakenSkillStats {
+            id: 16010,
+            name: "Crimson Slash".into(),
+            source_name: "Red Doom Narkiel".into(),
+            hit_count: 2,
+            shielded_hit_count: 1,
+            total_damage: 120_000,
+            shielded_damage: 20_000
+        };
+        let player = Player {
+            stats: PlayerStats {
+                total_damage: 1_000_000,
+                damage_taken_by_skill: [(skill.id, skill)].into(),
+                ..Default::default()
+            },
+            ..fixtures::player(1, Utc::now())
+        };
+
+        for encoding in [PayloadEncoding::Json, PayloadEncoding::MessagePack] {
+            let payload = encode(&player, encoding).unwrap();
+            let wire = serde_json::to_string(&payload).unwrap();
+            let payload: EncodedPayload = serde_json::from_str(&wire).unwrap();
+            let decoded: Player = decode(&payload).unwrap();
+
+            assert_eq!(decoded, player);
+        }
+    }
+}
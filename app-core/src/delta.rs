@@ -0,0 +1,533 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::*;
+
+/// Fight update as sent to the meter, a snapshot when subscribing and deltas afterwards.
+/// Externally tagged since internally tagged enums cannot deserialize maps with integer keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FightUpdateMessage {
+    Snapshot(FightUpdateSnapshot),
+    Delta(FightUpdateDelta)
+}
+
+impl FightUpdateMessage {
+    pub fn sequence(&self) -> u64 {
+        match self {
+            FightUpdateMessage::Snapshot(snapshot) => snapshot.sequence,
+            FightUpdateMessage::Delta(delta) => delta.sequence,
+        }
+    }
+}
+
+impl AppEvent for FightUpdateMessage {
+    fn event_name(&self) -> &'static str {
+        "fight-update-message"
+    }
+
+    /// Coalesces consecutive messages into one that the receiver can still apply in sequence.
+    fn merge(&mut self, newer: Self) {
+        let FightUpdateMessage::Delta(newer_delta) = newer else {
+            *self = newer;
+            return;
+        };
+
+        if newer_delta.base_sequence != self.sequence() {
+            *self = FightUpdateMessage::Delta(newer_delta);
+            return;
+        }
+
+        match self {
+            FightUpdateMessage::Snapshot(snapshot) => {
+                snapshot.sequence = newer_delta.sequence;
+                FightUpdateDecoder::apply_delta(&mut snapshot.update, newer_delta);
+            },
+            FightUpdateMessage::Delta(delta) => delta.merge(newer_delta),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FightUpdateSnapshot {
+    pub sequence: u64,
+    pub update: FightUpdate
+}
+
+/// Changes on top of the message with `base_sequence`, unchanged sections are `None` or empty.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FightUpdateDelta {
+    pub base_sequence: u64,
+    pub sequence: u64,
+    /// Stats without the logs, new log entries are sent in `esther_usage_log` and `death_timeline`.
+    pub stats: Option<EncounterStats>,
+    pub esther_usage_log: Vec<EstherUsageLog>,
+    pub death_timeline: Vec<DeathTimelineEntry>,
+    pub esthers: Option<Vec<Esther>>,
+    /// Added or changed players.
+    pub players: Vec<Player>,
+    /// Order of all players, sent when players were added, removed or reordered.
+    pub player_ids: Option<Vec<EntityId>>,
+    pub boss: Option<Boss>,
+    pub dps_timeline: Option<DpsTimelineDelta>,
+    pub hp_bar_changes: Vec<HpBarChange>,
+    pub phases: Vec<PhaseMarker>,
+    pub uptime: Option<UptimeStats>
+}
+
+impl FightUpdateDelta {
+    /// Combines with the delta that follows it, the result still applies on top of `base_sequence`.
+    pub fn merge(&mut self, newer: FightUpdateDelta) {
+        self.sequence = newer.sequence;
+        self.esther_usage_log.extend(newer.esther_usage_log);
+        self.death_timeline.extend(newer.death_timeline);
+        self.hp_bar_changes.extend(newer.hp_bar_changes);
+        self.phases.extend(newer.phases);
+
+        for player in newer.players {
+            match self.players.iter_mut().find(|changed| changed.id == player.id) {
+                Some(changed) => *changed = player,
+                None => self.players.push(player),
+            }
+        }
+
+        self.stats = newer.stats.or(self.stats.take());
+        self.esthers = newer.esthers.or(self.esthers.take());
+        self.player_ids = newer.player_ids.or(self.player_ids.take());
+        self.boss = newer.boss.or(self.boss.take());
+        self.uptime = newer.uptime.or(self.uptime.take());
+
+        self.dps_timeline = match (self.dps_timeline.take(), newer.dps_timeline) {
+            (Some(older), Some(newer)) => Some(older.merge(newer)),
+            (older, newer) => newer.or(older),
+        };
+    }
+}
+
+/// Dps timeline buckets from `start_bucket` onwards, earlier buckets are unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DpsTimelineDelta {
+    pub bucket_size_seconds: i64,
+    pub start_bucket: usize,
+    pub party_damage: Vec<i64>,
+    pub player_damage: HashMap<EntityId, Vec<i64>>
+}
+
+impl DpsTimelineDelta {
+    fn merge(self, newer: DpsTimelineDelta) -> DpsTimelineDelta {
+        if newer.start_bucket <= self.start_bucket {
+            return newer;
+        }
+
+        let kept = newer.start_bucket - self.start_bucket;
+        let combine = |older: Option<&Vec<i64>>, newer: Vec<i64>| {
+            let mut damage = older.cloned().unwrap_or_default();
+            damage.resize(kept, 0);
+            damage.extend(newer);
+            damage
+        };
+
+        DpsTimelineDelta {
+            bucket_size_seconds: newer.bucket_size_seconds,
+            start_bucket: self.start_bucket,
+            party_damage: combine(Some(&self.party_damage), newer.party_damage),
+            player_damage: newer.player_damage
+                .into_iter()
+                .map(|(id, damage)| (id, combine(self.player_damage.get(&id), damage)))
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeltaError {
+    MissingSnapshot,
+    SequenceGap { expected: u64, received: u64 }
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeltaError::MissingSnapshot => write!(f, "received a delta before any snapshot"),
+            DeltaError::SequenceGap { expected, received } => write!(f, "expected a delta on top of sequence {expected}, received one on top of {received}"),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+/// Turns consecutive fight updates into messages, diffing each against the previous one.
+#[derive(Debug, Default)]
+pub struct FightUpdateEncoder {
+    sequence: u64,
+    last: Option<FightUpdate>
+}
+
+impl FightUpdateEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the next message a snapshot, e.g. when a new subscriber appears.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    pub fn encode(&mut self, update: FightUpdate) -> FightUpdateMessage {
+        self.sequence += 1;
+
+        let message = match self.last.as_ref().filter(|last| Self::is_continuation(last, &update)) {
+            Some(last) => FightUpdateMessage::Delta(Self::diff(self.sequence, last, &update)),
+            None => FightUpdateMessage::Snapshot(FightUpdateSnapshot {
+                sequence: self.sequence,
+                update: update.clone()
+            }),
+        };
+
+        self.last = Some(update);
+
+        message
+    }
+
+    /// Append-only sections can only be diffed within the same encounter.
+    fn is_continuation(last: &FightUpdate, update: &FightUpdate) -> bool {
+        last.boss.id == update.boss.id
+            && last.stats.esther_usage_log.len() <= update.stats.esther_usage_log.len()
+            && last.stats.death_timeline.len() <= update.stats.death_timeline.len()
+            && last.boss_hp_timeline.hp_bar_changes.len() <= update.boss_hp_timeline.hp_bar_changes.len()
+            && last.boss_hp_timeline.phases.len() <= update.boss_hp_timeline.phases.len()
+            && last.dps_timeline.party_damage.len() <= update.dps_timeline.party_damage.len()
+    }
+
+    fn diff(sequence: u64, last: &FightUpdate, update: &FightUpdate) -> FightUpdateDelta {
+        let last_stats = Self::stats_without_logs(&last.stats);
+        let stats = Self::stats_without_logs(&update.stats);
+
+        let last_players: HashMap<EntityId, &Player> = last.players.iter().map(|player| (player.id, player)).collect();
+        let players = update.players
+            .iter()
+            .filter(|player| last_players.get(&player.id) != Some(player))
+            .cloned()
+            .collect();
+
+        let player_ids: Vec<EntityId> = update.players.iter().map(|player| player.id).collect();
+        let last_player_ids: Vec<EntityId> = last.players.iter().map(|player| player.id).collect();
+
+        FightUpdateDelta {
+            base_sequence: sequence - 1,
+            sequence,
+            stats: (stats != last_stats).then_some(stats),
+            esther_usage_log: update.stats.esther_usage_log[last.stats.esther_usage_log.len()..].to_vec(),
+            death_timeline: update.stats.death_timeline[last.stats.death_timeline.len()..].to_vec(),
+            esthers: (update.esthers != last.esthers).then(|| update.esthers.clone()),
+            players,
+            player_ids: (player_ids != last_player_ids).then_some(player_ids),
+            boss: (update.boss != last.boss).then(|| update.boss.clone()),
+            dps_timeline: Self::diff_dps_timeline(&last.dps_timeline, &update.dps_timeline),
+            hp_bar_changes: update.boss_hp_timeline.hp_bar_changes[last.boss_hp_timeline.hp_bar_changes.len()..].to_vec(),
+            phases: update.boss_hp_timeline.phases[last.boss_hp_timeline.phases.len()..].to_vec(),
+            uptime: (update.uptime != last.uptime).then(|| update.uptime.clone())
+        }
+    }
+
+    fn diff_dps_timeline(last: &DpsTimeline, timeline: &DpsTimeline) -> Option<DpsTimelineDelta> {
+        let first_change = |last: &[i64], current: &[i64]| -> Option<usize> {
+            current.iter()
+                .enumerate()
+                .position(|(index, damage)| last.get(index) != Some(damage))
+        };
+
+        let start_bucket = timeline.player_damage
+            .iter()
+            .filter_map(|(id, damage)| first_change(last.player_damage.get(id).map(Vec::as_slice).unwrap_or_default(), damage))
+            .chain(first_change(&last.party_damage, &timeline.party_damage))
+            .min();
+
+        let start_bucket = match start_bucket {
+            Some(start_bucket) => start_bucket,
+            None if last.bucket_size_seconds != timeline.bucket_size_seconds => timeline.party_damage.len(),
+            None => return None,
+        };
+
+        let tail = |damage: &Vec<i64>| damage.get(start_bucket..).map(<[i64]>::to_vec).unwrap_or_default();
+
+        Some(DpsTimelineDelta {
+            bucket_size_seconds: timeline.bucket_size_seconds,
+            start_bucket,
+            party_damage: tail(&timeline.party_damage),
+            player_damage: timeline.player_damage.iter().map(|(id, damage)| (*id, tail(damage))).collect()
+        })
+    }
+
+    fn stats_without_logs(stats: &EncounterStats) -> EncounterStats {
+        EncounterStats {
+            esther_usage_log: vec![],
+            death_timeline: vec![],
+            ..stats.clone()
+        }
+    }
+}
+
+/// Rebuilds the fight update on the receiving side, a sequence gap requires a new snapshot.
+#[derive(Debug, Default)]
+pub struct FightUpdateDecoder {
+    sequence: u64,
+    update: Option<FightUpdate>
+}
+
+impl FightUpdateDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&self) -> Option<&FightUpdate> {
+        self.update.as_ref()
+    }
+
+    pub fn apply(&mut self, message: FightUpdateMessage) -> Result<&FightUpdate, DeltaError> {
+        let delta = match message {
+            FightUpdateMessage::Snapshot(snapshot) => {
+                self.sequence = snapshot.sequence;
+                return Ok(self.update.insert(snapshot.update));
+            },
+            FightUpdateMessage::Delta(delta) => delta,
+        };
+
+        if self.update.is_none() {
+            return Err(DeltaError::MissingSnapshot);
+        }
+
+        if delta.base_sequence != self.sequence {
+            self.update = None;
+            return Err(DeltaError::SequenceGap { expected: self.sequence, received: delta.base_sequence });
+        }
+
+        self.sequence = delta.sequence;
+
+        let update = self.update.as_mut().ok_or(DeltaError::MissingSnapshot)?;
+        Self::apply_delta(update, delta);
+
+        Ok(update)
+    }
+
+    fn apply_delta(update: &mut FightUpdate, delta: FightUpdateDelta) {
+        if let Some(stats) = delta.stats {
+            let esther_usage_log = std::mem::take(&mut update.stats.esther_usage_log);
+            let death_timeline = std::mem::take(&mut update.stats.death_timeline);
+
+            update.stats = EncounterStats {
+                esther_usage_log,
+                death_timeline,
+                ..stats
+            };
+        }
+
+        update.stats.esther_usage_log.extend(delta.esther_usage_log);
+        update.stats.death_timeline.extend(delta.death_timeline);
+
+        if let Some(esthers) = delta.esthers {
+            update.esthers = esthers;
+        }
+
+        let player_ids = delta.player_ids.unwrap_or_else(|| update.players.iter().map(|player| player.id).collect());
+        let mut players: HashMap<EntityId, Player> = std::mem::take(&mut update.players)
+            .into_iter()
+            .map(|player| (player.id, player))
+            .collect();
+
+        for player in delta.players {
+            players.insert(player.id, player);
+        }
+
+        update.players = player_ids.iter().filter_map(|id| players.remove(id)).collect();
+
+        if let Some(boss) = delta.boss {
+            update.boss = boss;
+        }
+
+        if let Some(dps_timeline) = delta.dps_timeline {
+            Self::apply_dps_timeline(&mut update.dps_timeline, dps_timeline);
+        }
+
+        update.boss_hp_timeline.hp_bar_changes.extend(delta.hp_bar_changes);
+        update.boss_hp_timeline.phases.extend(delta.phases);
+
+        if let Some(uptime) = delta.uptime {
+            update.uptime = uptime;
+        }
+    }
+
+    fn apply_dps_timeline(timeline: &mut DpsTimeline, delta: DpsTimelineDelta) {
+        let start_bucket = delta.start_bucket;
+        timeline.bucket_size_seconds = delta.bucket_size_seconds;
+        timeline.party_damage.truncate(start_bucket);
+        timeline.party_damage.extend(delta.party_damage);
+
+        timeline.player_damage.retain(|id, _| delta.player_damage.contains_key(id));
+
+        for (id, damage) in delta.player_damage {
+            let player_damage = timeline.player_damage.entry(id).or_default();
+            player_damage.resize(start_bucket, 0);
+            player_damage.extend(damage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+
+    use crate::{aggregator::Aggregator, events::*, fixtures::*};
+
+    use super::*;
+
+    const BOSS_ID: EntityId = 1;
+    const PLAYER_ID: EntityId = 2;
+    const SUPPORT_ID: EntityId = 3;
+
+    fn aggregator() -> Aggregator {
+        let now = Utc::now();
+        let mut aggregator = Aggregator::new();
+
+        for id in [PLAYER_ID, SUPPORT_ID] {
+            aggregator.add_player(player(id, now));
+        }
+
+        aggregator.set_boss(boss(BOSS_ID, 10, 100, now));
+
+        aggregator
+    }
+
+    fn damage(source_id: EntityId, damage: i64, recorded_on: chrono::DateTime<Utc>) -> EncounterEvent {
+        EncounterEvent::Damage(DamageEvent {
+            source_id,
+            target_id: BOSS_ID,
+            skill: skill(),
+            damage,
+            shield_damage: 0,
+            hit_flag: HitFlag::Normal,
+            hit_option: HitOption::BackAttack,
+            is_hyper_awakening: false,
+            is_counter: false,
+            stagger: 0,
+            destruction: 0,
+            recorded_on
+        })
+    }
+
+    #[test]
+    fn test_deltas_rebuild_every_update() {
+        let now = Utc::now();
+        let mut aggregator = aggregator();
+        let mut encoder = FightUpdateEncoder::new();
+        let mut decoder = FightUpdateDecoder::new();
+
+        for tick in 0..12 {
+            let recorded_on = now + TimeDelta::seconds(tick);
+            aggregator.process(damage(PLAYER_ID, 40, recorded_on));
+
+            if tick % 3 == 0 {
+                aggregator.process(damage(SUPPORT_ID, 10, recorded_on));
+            }
+
+            if tick == 6 {
+                aggregator.process(EncounterEvent::Death(DeathEvent {
+                    target_id: SUPPORT_ID,
+                    recorded_on
+                }));
+            }
+
+            aggregator.update(recorded_on);
+
+            let update = aggregator.to_fight_update().unwrap();
+            let message = encoder.encode(update.clone());
+
+            assert_eq!(matches!(message, FightUpdateMessage::Snapshot(_)), tick == 0);
+            assert_eq!(decoder.apply(message).unwrap(), &update);
+        }
+    }
+
+    #[test]
+    fn test_delta_skips_unchanged_sections() {
+        let now = Utc::now();
+        let mut aggregator = aggregator();
+        let mut encoder = FightUpdateEncoder::new();
+
+        aggregator.process(damage(PLAYER_ID, 10, now));
+        aggregator.process(damage(SUPPORT_ID, 10, now));
+        aggregator.update(now);
+        encoder.encode(aggregator.to_fight_update().unwrap());
+
+        aggregator.process(damage(PLAYER_ID, 10, now));
+        aggregator.update(now);
+
+        let FightUpdateMessage::Delta(delta) = encoder.encode(aggregator.to_fight_update().unwrap()) else {
+            panic!("expected a delta");
+        };
+
+        assert_eq!(delta.sequence, 2);
+        assert!(delta.esthers.is_none());
+        assert!(delta.players.iter().any(|player| player.id == PLAYER_ID));
+        assert!(delta.hp_bar_changes.is_empty());
+        assert_eq!(delta.dps_timeline.unwrap().start_bucket, 0);
+    }
+
+    #[test]
+    fn test_merged_messages_rebuild_the_latest_update() {
+        let now = Utc::now();
+        let mut aggregator = aggregator();
+        let mut encoder = FightUpdateEncoder::new();
+        let mut decoder = FightUpdateDecoder::new();
+        let mut pending: Option<FightUpdateMessage> = None;
+
+        for tick in 0..12 {
+            let recorded_on = now + TimeDelta::seconds(tick);
+            aggregator.process(damage(PLAYER_ID, 40, recorded_on));
+
+            if tick == 5 {
+                aggregator.process(EncounterEvent::Death(DeathEvent {
+                    target_id: SUPPORT_ID,
+                    recorded_on
+                }));
+            }
+
+            aggregator.update(recorded_on);
+
+            let update = aggregator.to_fight_update().unwrap();
+            let message = encoder.encode(update.clone());
+
+            match pending.as_mut() {
+                Some(pending) => pending.merge(message),
+                None => pending = Some(message),
+            }
+
+            if tick % 4 == 3 {
+                assert_eq!(decoder.apply(pending.take().unwrap()).unwrap(), &update);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequence_gap_requires_snapshot() {
+        let now = Utc::now();
+        let mut aggregator = aggregator();
+        let mut encoder = FightUpdateEncoder::new();
+        let mut decoder = FightUpdateDecoder::new();
+
+        aggregator.update(now);
+        let snapshot = encoder.encode(aggregator.to_fight_update().unwrap());
+        let _lost = encoder.encode(aggregator.to_fight_update().unwrap());
+        let delta = encoder.encode(aggregator.to_fight_update().unwrap());
+
+        assert_eq!(decoder.apply(delta.clone()), Err(DeltaError::MissingSnapshot));
+        decoder.apply(snapshot).unwrap();
+        assert_eq!(decoder.apply(delta), Err(DeltaError::SequenceGap { expected: 1, received: 2 }));
+        assert!(decoder.update().is_none());
+
+        encoder.reset();
+        let resync = encoder.encode(aggregator.to_fight_update().unwrap());
+        assert!(matches!(resync, FightUpdateMessage::Snapshot(_)));
+        assert!(decoder.apply(resync).is_ok());
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::*;
+
+/// Domain events consumed by [`crate::aggregator::Aggregator`].
+///
+/// Both the fight simulator and live packet capture translate what they observe into these,
+/// so the encounter stats are computed in one place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum EncounterEvent {
+    SkillCast(SkillCastEvent),
+    Damage(DamageEvent),
+    Heal(HealEvent),
+    Death(DeathEvent),
+    Resurrect(ResurrectEvent),
+    StatusEffect(StatusEffectEvent),
+    Esther(EstherEvent),
+    BossShield(BossShieldEvent),
+    PhaseTransition(PhaseTransitionEvent),
+}
+
+impl EncounterEvent {
+    pub fn recorded_on(&self) -> DateTime<Utc> {
+        match self {
+            EncounterEvent::SkillCast(event) => event.recorded_on,
+            EncounterEvent::Damage(event) => event.recorded_on,
+            EncounterEvent::Heal(event) => event.recorded_on,
+            EncounterEvent::Death(event) => event.recorded_on,
+            EncounterEvent::Resurrect(event) => event.recorded_on,
+            EncounterEvent::StatusEffect(event) => event.recorded_on,
+            EncounterEvent::Esther(event) => event.recorded_on,
+            EncounterEvent::BossShield(event) => event.recorded_on,
+            EncounterEvent::PhaseTransition(event) => event.recorded_on,
+        }
+    }
+}
+
+/// Start of a skill cast by `source_id`, a cast may result in any number of hits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillCastEvent {
+    pub source_id: EntityId,
+    pub skill: Skill,
+    pub recorded_on: DateTime<Utc>
+}
+
+/// A hit of `source_id` on `target_id`.
+/// Player hits count towards damage dealt, hits on players towards damage taken.
+/// Hits on the boss deplete its shield before its hp, `damage` is the whole hit and `shield_damage`
+/// the part of it the boss shield absorbed. Hits on players report the part absorbed by their shields
+/// separately in `shield_damage`, on top of `damage`.
+/// `stagger` and `destruction` are the amounts the hit contributed towards staggering
+/// the target and destroying its parts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageEvent {
+    pub source_id: EntityId,
+    pub target_id: EntityId,
+    pub skill: Skill,
+    pub damage: i64,
+    pub shield_damage: i64,
+    pub hit_flag: HitFlag,
+    pub hit_option: HitOption,
+    pub is_hyper_awakening: bool,
+    pub is_counter: bool,
+    pub stagger: i64,
+    pub destruction: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealEvent {
+    pub source_id: EntityId,
+    pub target_id: EntityId,
+    pub amount: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeathEvent {
+    pub target_id: EntityId,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResurrectEvent {
+    pub target_id: EntityId,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StatusEffectEventKind {
+    Applied,
+    Removed
+}
+
+/// Status effect put on or taken off `target_id`, `shield_hp` is the amount
+/// granted when the status effect is a shield and 0 otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffectEvent {
+    pub source_id: EntityId,
+    pub target_id: EntityId,
+    pub status_effect_id: StatusEffectId,
+    pub kind: StatusEffectEventKind,
+    pub shield_hp: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+/// Esther skill triggered by player `source_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EstherEvent {
+    pub source_id: EntityId,
+    pub esther_id: EntityId,
+    pub damage: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+/// Shield put up by the boss, replaces the remaining one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BossShieldEvent {
+    pub shield_hp: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTransitionEvent {
+    pub phase: u32,
+    pub recorded_on: DateTime<Utc>
+}
@@ -0,0 +1,51 @@
+//! Players, boss and skills shared by the tests and the benches, built with the `fixtures` feature outside of tests.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::*;
+
+/// Berserker named after its id, with no stats yet.
+pub fn player(id: EntityId, now: DateTime<Utc>) -> Player {
+    Player {
+        id,
+        name: format!("Player{id}"),
+        class_color: "#FFFFFF".into(),
+        class_name: "Berserker".into(),
+        class_id: 102,
+        created_on: now,
+        stats: PlayerStats::default(),
+        death_log: DeathLog::default()
+    }
+}
+
+/// Red Doom Narkiel at full hp, split in `hp_bars` bars of `hp_per_bar` each.
+pub fn boss(id: EntityId, hp_bars: i64, hp_per_bar: i64, now: DateTime<Utc>) -> Boss {
+    let max_hp = hp_bars * hp_per_bar;
+
+    Boss {
+        id,
+        npc_id: 485000,
+        name: "Red Doom Narkiel".into(),
+        raid_gate: None,
+        image_key: None,
+        stats: BossStats {
+            max_hp,
+            hp: max_hp,
+            max_hp_bars: hp_bars,
+            hp_bars,
+            hp_per_bar: hp_per_bar as f32,
+            hp_percentage: 100.0,
+            updated_on: now,
+            ..Default::default()
+        },
+        created_on: now
+    }
+}
+
+pub fn skill() -> Skill {
+    Skill {
+        id: 16010,
+        name: "Red Dust".into(),
+        icon: "red_dust.png".into()
+    }
+}
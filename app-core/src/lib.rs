@@ -1,3 +1,12 @@
+pub mod aggregator;
+pub mod codec;
+pub mod delta;
+pub mod events;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 pub mod models;
+pub mod names;
 pub mod utils;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod tabs;
+pub mod ttk;
\ No newline at end of file
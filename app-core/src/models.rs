@@ -1,17 +1,16 @@
-use std::{collections::HashMap, fmt::format};
+use std::collections::HashMap;
 
 use app_macros::AppEvent;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::abbreviate_number;
-
 pub type EntityId = u64;
 pub type ClassId = u32;
 pub type SkillId = u32;
 pub type NpcId = u32;
+pub type StatusEffectId = u32;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum HitFlag {
     Normal = 0,
@@ -30,7 +29,7 @@ pub enum HitFlag {
     Max = 13,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum HitOption {
     None,
@@ -42,18 +41,45 @@ pub enum HitOption {
 
 pub trait AppEvent: Serialize + Clone {
     fn event_name(&self) -> &'static str;
+
+    /// Folds a newer event of the same kind into this one when emission is coalesced,
+    /// by default the newer event replaces it.
+    fn merge(&mut self, newer: Self) where Self: Sized {
+        *self = newer;
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Difficulty {
+    #[default]
+    Normal,
+    Hard,
+    Nightmare
+}
+
+/// Raid gate an encounter takes place in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaidGate {
+    pub raid: String,
+    pub gate: u8,
+    pub difficulty: Difficulty
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Boss {
     pub id: EntityId,
     pub npc_id: NpcId,
     pub name: String,
+    /// `None` for npcs missing from the npc dataset.
+    pub raid_gate: Option<RaidGate>,
+    /// Name of the boss image, `public/images/bosses/<image_key>.png` in the web.
+    pub image_key: Option<String>,
     pub stats: BossStats,
     pub created_on: DateTime<Utc>
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct BossStats {
     pub max_hp: i64,
     pub hp: i64,
@@ -63,6 +89,8 @@ pub struct BossStats {
     pub hp_per_bar: f32,
     pub hp_percentage: f32,
     pub damage_taken: i64,
+    /// Part of `damage_taken` absorbed by the boss shields.
+    pub shield_damage_taken: i64,
     pub total_damage_dealt: i64,
     pub updated_on: DateTime<Utc>
 }
@@ -84,7 +112,16 @@ pub struct DeathLog {
     pub is_dead: bool,
     pub death_count: u32,
     pub recorded_on: Option<DateTime<Utc>>,
-    pub duration: Option<Duration>
+    pub duration: Option<Duration>,
+    pub windows: Vec<DeathWindow>,
+    /// Time spent dead across all windows, including the current one.
+    pub total_dead_ms: i64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeathWindow {
+    pub died_on: DateTime<Utc>,
+    pub resurrected_on: Option<DateTime<Utc>>
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -110,13 +147,51 @@ pub struct Skill {
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EncounterStats {
-    pub duration_mmss: String,
-    pub time_to_kill_mmss: String,
-    pub party_dps: Dps,
+    pub duration: Duration,
+    /// Projection from the recent boss hp loss, `None` until the boss has been losing hp for a while.
+    pub time_to_kill: Option<TimeToKill>,
+    pub party_dps: i64,
+    pub party_rolling_dps: i64,
     pub top_damage: i64,
     pub total_player_damage: i64,
     pub total_esther_damage: i64,
     pub total_taken_damage: i64,
+    pub total_stagger: i64,
+    pub total_destruction: i64,
+    pub esther_usage_log: Vec<EstherUsageLog>,
+    pub death_timeline: Vec<DeathTimelineEntry>,
+}
+
+/// Time to kill with its 95% confidence interval, `upper_bound` is `None` when
+/// the hp loss is too erratic to rule out the boss not dying at all.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeToKill {
+    pub estimate: Duration,
+    pub lower_bound: Duration,
+    pub upper_bound: Option<Duration>,
+    /// Hp and shield lost per second while the boss was taking damage.
+    pub hp_loss_per_second: i64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EstherUsageLog {
+    pub esther_id: EntityId,
+    pub player_id: EntityId,
+    pub damage: i64,
+    pub recorded_on: DateTime<Utc>
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DeathTimelineKind {
+    Death,
+    Resurrect
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeathTimelineEntry {
+    pub player_id: EntityId,
+    pub kind: DeathTimelineKind,
+    pub recorded_on: DateTime<Utc>
 }
 
 #[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
@@ -126,7 +201,10 @@ pub struct PlayerStats {
     pub top_damage: i64,
     pub total_damage: i64,
     pub damage_percentage: f32,
-    pub dps: Dps,
+    pub dps: i64,
+    /// Dps over the time the player was alive.
+    pub alive_dps: i64,
+    pub rolling_dps: i64,
     pub back_attacks_total_damage: i64,
     pub front_attacks_total_damage: i64,
     pub non_positional_attacks_total_damage: i64,
@@ -137,7 +215,34 @@ pub struct PlayerStats {
     pub updated_on: DateTime<Utc>,
     pub damage_taken: i64,
     pub damage_taken_percentage: f32,
-    pub skills: PlayerSkillsStats
+    pub healing_done: i64,
+    pub healing_received: i64,
+    pub stagger: i64,
+    /// Share of the party's stagger.
+    pub stagger_percentage: f32,
+    pub destruction: i64,
+    /// Share of the party's part destruction.
+    pub destruction_percentage: f32,
+    pub skills: PlayerSkillsStats,
+    pub damage_taken_by_skill: HashMap<SkillId, DamageTakenSkillStats>,
+    pub shield_given: i64,
+    pub shield_received: i64,
+    /// Damage absorbed by the shields this player gave.
+    pub damage_absorbed_given: i64,
+    /// Damage absorbed by the shields on this player.
+    pub damage_absorbed_received: i64
+}
+
+/// Damage a player took from a single incoming skill, `shielded_damage` is the part absorbed by shields.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct DamageTakenSkillStats {
+    pub id: SkillId,
+    pub name: String,
+    pub source_name: String,
+    pub hit_count: u32,
+    pub shielded_hit_count: u32,
+    pub total_damage: i64,
+    pub shielded_damage: i64
 }
 
 #[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
@@ -145,7 +250,7 @@ pub struct PlayerSkillsStats {
     pub counter_count: i64,
     pub hit_count: u32,
     pub crit_count: u32,
-    pub skill: HashMap<String, PlayerSkillStats>
+    pub skill: HashMap<SkillId, PlayerSkillStats>
 }
 
 #[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
@@ -153,10 +258,22 @@ pub struct PlayerSkillStats {
     pub id: SkillId,
     pub name: String,
     pub icon: String,
+    pub cast_count: u32,
     pub hit_count: u32,
     pub crit_count: u32,
     pub crit_damage: i64,
+    /// Share of the skill's damage dealt by critical hits.
+    pub crit_damage_percentage: f32,
     pub total_damage: i64,
+    pub max_hit: i64,
+    pub average_hit: i64,
+    pub dps: i64,
+    /// Share of the player's damage dealt by this skill.
+    pub damage_percentage: f32,
+    pub back_attack_count: u32,
+    pub front_attack_count: u32,
+    pub back_attack_rate: f32,
+    pub front_attack_rate: f32,
     // pub cast_log: Vec<SkillCastLog>
 }
 
@@ -174,47 +291,115 @@ pub struct SkillHit {
     pub is_critical: bool,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Deserialize)]
-pub struct Dps {
-    pub raw: i64,
-    pub abbreviated: String,
-    pub value: f64,
-    pub unit: char
-}
-
-impl Dps {
-    pub fn new(total_damage: i64, duration_seconds: i64) -> Self {
-        if duration_seconds == 0 {
-            return Self::default()
-        }
-
-        let raw = total_damage / duration_seconds;
-        let (value, unit) = abbreviate_number(raw);
-
-        Self {
-            raw,
-            abbreviated: format!("{:.1}{}", value, unit),
-            value,
-            unit
-        }
-    }
+/// Damage dealt in consecutive buckets of `bucket_size_seconds` since the start of the encounter.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct DpsTimeline {
+    pub bucket_size_seconds: i64,
+    pub party_damage: Vec<i64>,
+    pub player_damage: HashMap<EntityId, Vec<i64>>
 }
 
-impl Default for Dps {
-    fn default() -> Self {
-        Dps {
-            raw: 0,
-            abbreviated: String::from("0.0"),
-            value: 0.0,
-            unit: ' ',
-        }
+impl DpsTimeline {
+    pub fn party_dps(&self) -> Vec<i64> {
+        self.party_damage.iter().map(|damage| damage / self.bucket_size_seconds).collect()
     }
+
+    pub fn player_dps(&self, player_id: EntityId) -> Vec<i64> {
+        self.player_damage
+            .get(&player_id)
+            .map(|damage| damage.iter().map(|damage| damage / self.bucket_size_seconds).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Offsets are milliseconds since the start of the encounter.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct BossHpSample {
+    pub offset_ms: i64,
+    pub hp: i64,
+    pub hp_bars: i64,
+    pub shield_hp: i64
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct HpBarChange {
+    pub offset_ms: i64,
+    pub hp_bars: i64
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct PhaseMarker {
+    pub offset_ms: i64,
+    pub phase: u32
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct BossHpTimeline {
+    pub samples: Vec<BossHpSample>,
+    pub hp_bar_changes: Vec<HpBarChange>,
+    pub phases: Vec<PhaseMarker>
+}
+
+/// [`BossHpTimeline`] without the periodic samples, sent with every [`FightUpdate`].
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct CompactBossHpTimeline {
+    pub hp_bar_changes: Vec<HpBarChange>,
+    pub phases: Vec<PhaseMarker>
 }
 
-#[derive(Debug, AppEvent, Clone, Serialize, Deserialize)]
+/// Status effect whose uptime is reported, e.g. a synergy.
+#[derive(Debug, Clone, Serialize, PartialEq, Deserialize)]
+pub struct TrackedStatusEffect {
+    pub id: StatusEffectId,
+    pub name: String
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct StatusEffectUptime {
+    pub id: StatusEffectId,
+    pub name: String,
+    pub uptime_ms: i64,
+    pub percentage: f32
+}
+
+/// Uptime of the tracked status effects on the boss and on each player across the fight.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+pub struct UptimeStats {
+    pub boss: Vec<StatusEffectUptime>,
+    pub players: HashMap<EntityId, Vec<StatusEffectUptime>>
+}
+
+#[derive(Debug, AppEvent, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FightUpdate {
     pub stats: EncounterStats,
     pub esthers: Vec<Esther>,
     pub players: Vec<Player>,
-    pub boss: Boss
+    pub boss: Boss,
+    pub dps_timeline: DpsTimeline,
+    pub boss_hp_timeline: CompactBossHpTimeline,
+    pub uptime: UptimeStats
+}
+
+/// Number of override entries per dataset after the data overrides were reloaded,
+/// the meter requests a snapshot to show the new names and icons.
+#[derive(Debug, AppEvent, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataReloaded {
+    pub classes: usize,
+    pub skills: usize,
+    pub npcs: usize,
+    pub status_effects: usize
+}
+
+/// Finished encounter as stored for the logs window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Encounter {
+    pub started_on: DateTime<Utc>,
+    pub ended_on: DateTime<Utc>,
+    pub stats: EncounterStats,
+    pub esthers: Vec<Esther>,
+    pub players: Vec<Player>,
+    pub boss: Boss,
+    pub dps_timeline: DpsTimeline,
+    pub boss_hp_timeline: BossHpTimeline,
+    pub uptime: UptimeStats
 }
\ No newline at end of file
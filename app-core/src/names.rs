@@ -0,0 +1,16 @@
+use crate::models::*;
+
+/// Display names and icons of the game data by id.
+///
+/// Implemented by the data crate over its datasets, the aggregator and the fight simulator
+/// use it to refresh what they captured when the datasets change at runtime.
+pub trait NameLookup {
+    fn class_name(&self, class_id: ClassId) -> Option<String>;
+
+    /// Skill with its current name and icon, `None` for skills without a name.
+    fn skill(&self, skill_id: SkillId) -> Option<Skill>;
+
+    fn npc_name(&self, npc_id: NpcId) -> Option<String>;
+
+    fn status_effect_name(&self, status_effect_id: StatusEffectId) -> Option<String>;
+}
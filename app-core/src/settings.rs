@@ -1,18 +1,68 @@
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
-use crate::models::ClassId;
+use crate::{codec::PayloadEncoding, models::ClassId};
 
 #[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
-    pub general: GeneralSettings
+    pub general: GeneralSettings,
+    pub format: FormatSettings
 }
 
-#[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
+/// Keys missing from a settings file written by an older version take their default.
+#[derive(Debug, Clone, Serialize, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct GeneralSettings {
     port: u16,
     always_on_top: bool,
+    /// Maximum number of meter updates emitted per second, read once when the app starts.
+    pub frame_rate: u32,
+    /// Encoding of the event payloads sent to the web, read once when the app starts.
+    pub payload_encoding: PayloadEncoding,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            port: 6040,
+            always_on_top: true,
+            frame_rate: 30,
+            payload_encoding: PayloadEncoding::MessagePack
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, Serialize, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeFormat {
+    /// `mm:ss`, an hour or more is shown as `∞`.
+    #[default]
+    MinutesSeconds,
+    Seconds
+}
+
+/// How numbers and durations are displayed, models only carry raw values.
+#[derive(Debug, Clone, Serialize, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatSettings {
+    /// Shows large numbers as e.g. `1.2m` instead of `1200000`.
+    pub abbreviate_numbers: bool,
+    /// Decimal places of abbreviated numbers.
+    pub decimals: usize,
+    pub percentage_decimals: usize,
+    pub time_format: TimeFormat
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self {
+            abbreviate_numbers: true,
+            decimals: 1,
+            percentage_decimals: 2,
+            time_format: TimeFormat::MinutesSeconds
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, PartialEq, Deserialize)]
@@ -20,4 +70,22 @@ pub struct ColorSettings {
     local: String,
     class_colors: FxHashMap<ClassId, String>,
     default_class_colors: FxHashMap<ClassId, String>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_general_settings_without_newer_keys_keep_the_others() {
+        let settings: Settings = serde_json::from_str(r#"{
+            "general": { "port": 6041, "alwaysOnTop": false },
+            "format": { "abbreviateNumbers": false, "decimals": 2, "percentageDecimals": 1, "timeFormat": "seconds" }
+        }"#).unwrap();
+
+        assert_eq!(settings.general.port, 6041);
+        assert!(!settings.general.always_on_top);
+        assert_eq!(settings.general.frame_rate, 30);
+        assert_eq!(settings.general.payload_encoding, PayloadEncoding::MessagePack);
+        assert_eq!(settings.format.time_format, TimeFormat::Seconds);
+    }
+}
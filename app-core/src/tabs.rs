@@ -0,0 +1,182 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{models::*, utils::percentage};
+
+/// Damage taken by a single player from one incoming skill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageTakenPlayerShare {
+    pub player_id: EntityId,
+    pub player_name: String,
+    pub class_color: String,
+    pub total_damage: i64,
+    pub shielded_damage: i64,
+    pub percentage: f32
+}
+
+/// Incoming skill with its share of the party's damage taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageTakenSkillRow {
+    pub id: SkillId,
+    pub name: String,
+    pub source_name: String,
+    pub hit_count: u32,
+    pub shielded_hit_count: u32,
+    pub total_damage: i64,
+    pub shielded_damage: i64,
+    pub percentage: f32,
+    pub players: Vec<DamageTakenPlayerShare>
+}
+
+/// Player with their damage taken split by incoming skill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageTakenPlayerRow {
+    pub id: EntityId,
+    pub name: String,
+    pub class_color: String,
+    pub total_damage: i64,
+    pub shielded_damage: i64,
+    pub percentage: f32,
+    pub skills: Vec<DamageTakenSkillStats>
+}
+
+/// Damage taken tab, rows are sorted by total damage descending.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageTakenTab {
+    pub total_damage: i64,
+    pub total_shielded_damage: i64,
+    pub by_skill: Vec<DamageTakenSkillRow>,
+    pub by_player: Vec<DamageTakenPlayerRow>
+}
+
+impl DamageTakenTab {
+    pub fn new(players: &[Player]) -> Self {
+        let mut tab = Self::default();
+        let mut by_skill: HashMap<SkillId, DamageTakenSkillRow> = HashMap::new();
+
+        for player in players {
+            let mut skills: Vec<_> = player.stats.damage_taken_by_skill.values().cloned().collect();
+            skills.sort_by_key(|skill| Reverse(skill.total_damage));
+
+            let total_damage = skills.iter().map(|skill| skill.total_damage).sum();
+            let shielded_damage = skills.iter().map(|skill| skill.shielded_damage).sum();
+
+            for skill in &skills {
+                let row = by_skill.entry(skill.id).or_insert_with(|| DamageTakenSkillRow {
+                    id: skill.id,
+                    name: skill.name.clone(),
+                    source_name: skill.source_name.clone(),
+                    hit_count: 0,
+                    shielded_hit_count: 0,
+                    total_damage: 0,
+                    shielded_damage: 0,
+                    percentage: 0.0,
+                    players: vec![]
+                });
+
+                row.hit_count += skill.hit_count;
+                row.shielded_hit_count += skill.shielded_hit_count;
+                row.total_damage += skill.total_damage;
+                row.shielded_damage += skill.shielded_damage;
+                row.players.push(DamageTakenPlayerShare {
+                    player_id: player.id,
+                    player_name: player.name.clone(),
+                    class_color: player.class_color.clone(),
+                    total_damage: skill.total_damage,
+                    shielded_damage: skill.shielded_damage,
+                    percentage: 0.0
+                });
+            }
+
+            tab.total_damage += total_damage;
+            tab.total_shielded_damage += shielded_damage;
+            tab.by_player.push(DamageTakenPlayerRow {
+                id: player.id,
+                name: player.name.clone(),
+                class_color: player.class_color.clone(),
+                total_damage,
+                shielded_damage,
+                percentage: 0.0,
+                skills
+            });
+        }
+
+        for row in &mut tab.by_player {
+            row.percentage = percentage(row.total_damage, tab.total_damage);
+        }
+
+        tab.by_player.sort_by_key(|row| Reverse(row.total_damage));
+
+        tab.by_skill = by_skill.into_values().collect();
+
+        for row in &mut tab.by_skill {
+            row.percentage = percentage(row.total_damage, tab.total_damage);
+
+            for share in &mut row.players {
+                share.percentage = percentage(share.total_damage, row.total_damage);
+            }
+
+            row.players.sort_by_key(|share| Reverse(share.total_damage));
+        }
+
+        tab.by_skill.sort_by(|a, b| b.total_damage.cmp(&a.total_damage).then(a.id.cmp(&b.id)));
+
+        tab
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::fixtures;
+
+    use super::*;
+
+    fn skill_stats(id: SkillId, total_damage: i64, shielded_damage: i64) -> DamageTakenSkillStats {
+        DamageTakenSkillStats {
+            id,
+            name: format!("Skill{id}"),
+            source_name: "Red Doom Narkiel".into(),
+            hit_count: 1,
+            shielded_hit_count: (shielded_damage > 0) as u32,
+            total_damage,
+            shielded_damage
+        }
+    }
+
+    fn player(id: EntityId, skills: Vec<DamageTakenSkillStats>) -> Player {
+        Player {
+            stats: PlayerStats {
+                damage_taken_by_skill: skills.into_iter().map(|skill| (skill.id, skill)).collect(),
+                ..Default::default()
+            },
+            ..fixtures::player(id, Utc::now())
+        }
+    }
+
+    #[test]
+    fn test_damage_taken_tab_groups_by_skill_and_player() {
+        let players = vec![
+            player(1, vec![skill_stats(10, 100, 0), skill_stats(20, 300, 50)]),
+            player(2, vec![skill_stats(10, 500, 0)]),
+        ];
+
+        let tab = DamageTakenTab::new(&players);
+
+        assert_eq!(tab.total_damage, 900);
+        assert_eq!(tab.total_shielded_damage, 50);
+
+        assert_eq!(tab.by_skill.len(), 2);
+        assert_eq!(tab.by_skill[0].id, 10);
+        assert_eq!(tab.by_skill[0].total_damage, 600);
+        assert_eq!(tab.by_skill[0].hit_count, 2);
+        assert_eq!(tab.by_skill[0].players[0].player_id, 2);
+        assert_eq!(tab.by_skill[1].shielded_hit_count, 1);
+
+        assert_eq!(tab.by_player[0].id, 2);
+        assert_eq!(tab.by_player[1].skills[0].id, 20);
+        assert!((tab.by_player[1].percentage - 4.0 / 9.0).abs() < 1e-6);
+    }
+}
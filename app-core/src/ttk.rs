@@ -0,0 +1,151 @@
+use chrono::TimeDelta;
+
+use crate::models::{BossHpSample, TimeToKill};
+
+/// z-score of a two-sided 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+const MIN_POINTS: usize = 3;
+
+/// Projects the time to kill from the boss hp samples of the last `window`.
+///
+/// Fits a least squares line through the damage the boss took over the time it was taking damage,
+/// so hp and shield lost count the same, while intervals without hp loss (invulnerable or
+/// untargetable phases, shields being restored) are left out instead of dragging the rate down.
+/// Returns `None` until there are enough samples with hp loss or when the boss is dead.
+pub fn estimate_time_to_kill(samples: &[BossHpSample], window: TimeDelta) -> Option<TimeToKill> {
+    let last = samples.last()?;
+    let remaining_hp = effective_hp(last);
+
+    if remaining_hp <= 0 {
+        return None;
+    }
+
+    let window_started_ms = last.offset_ms - window.num_milliseconds();
+    let recent = &samples[samples.partition_point(|sample| sample.offset_ms < window_started_ms)..];
+
+    let mut points = vec![(0.0, 0.0)];
+    let mut active_ms = 0;
+    let mut hp_lost = 0;
+
+    for pair in recent.windows(2) {
+        let lost = effective_hp(&pair[0]) - effective_hp(&pair[1]);
+
+        if lost <= 0 {
+            continue;
+        }
+
+        active_ms += pair[1].offset_ms - pair[0].offset_ms;
+        hp_lost += lost;
+        points.push((active_ms as f64, hp_lost as f64));
+    }
+
+    let (rate, standard_error) = fit_rate(&points)?;
+    let remaining_hp = remaining_hp as f64;
+    let upper_rate = rate + CONFIDENCE_Z * standard_error;
+    let lower_rate = rate - CONFIDENCE_Z * standard_error;
+
+    Some(TimeToKill {
+        estimate: TimeDelta::milliseconds((remaining_hp / rate).round() as i64),
+        lower_bound: TimeDelta::milliseconds((remaining_hp / upper_rate).round() as i64),
+        upper_bound: (lower_rate > 0.0).then(|| TimeDelta::milliseconds((remaining_hp / lower_rate).round() as i64)),
+        hp_loss_per_second: (rate * 1000.0).round() as i64
+    })
+}
+
+fn effective_hp(sample: &BossHpSample) -> i64 {
+    sample.hp + sample.shield_hp
+}
+
+/// Slope of the least squares line through `points` and its standard error,
+/// `None` when there are too few points or the slope is not a loss.
+fn fit_rate(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < MIN_POINTS {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+    if sxx <= 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+
+    if slope <= 0.0 {
+        return None;
+    }
+
+    let intercept = mean_y - slope * mean_x;
+    let residuals: f64 = points.iter().map(|(x, y)| (y - intercept - slope * x).powi(2)).sum();
+    let standard_error = (residuals / (count - 2.0) / sxx).sqrt();
+
+    Some((slope, standard_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(offset_seconds: i64, hp: i64, shield_hp: i64) -> BossHpSample {
+        BossHpSample {
+            offset_ms: offset_seconds * 1000,
+            hp,
+            hp_bars: 0,
+            shield_hp
+        }
+    }
+
+    #[test]
+    fn test_steady_hp_loss_gives_exact_estimate() {
+        let samples: Vec<_> = (0..10).map(|second| sample(second, 1000 - second * 10, 0)).collect();
+
+        let time_to_kill = estimate_time_to_kill(&samples, TimeDelta::seconds(30)).unwrap();
+
+        assert_eq!(time_to_kill.hp_loss_per_second, 10);
+        assert_eq!(time_to_kill.estimate, TimeDelta::seconds(91));
+        assert_eq!(time_to_kill.lower_bound, TimeDelta::seconds(91));
+        assert_eq!(time_to_kill.upper_bound, Some(TimeDelta::seconds(91)));
+    }
+
+    #[test]
+    fn test_invulnerable_phase_and_shield_are_accounted_for() {
+        let mut samples: Vec<_> = (0..5).map(|second| sample(second, 1000 - second * 10, 0)).collect();
+        samples.extend((5..20).map(|second| sample(second, 960, 0)));
+        samples.push(sample(20, 960, 100));
+        samples.extend((21..26).map(|second| sample(second, 960, 100 - (second - 20) * 10)));
+
+        let time_to_kill = estimate_time_to_kill(&samples, TimeDelta::seconds(60)).unwrap();
+
+        assert_eq!(time_to_kill.hp_loss_per_second, 10);
+        assert_eq!(time_to_kill.estimate, TimeDelta::seconds(101));
+    }
+
+    #[test]
+    fn test_bounds_widen_with_noisy_hp_loss() {
+        let losses = [5, 15, 8, 12, 6, 14, 9, 11];
+        let mut hp = 10_000;
+        let mut samples = vec![sample(0, hp, 0)];
+
+        for (second, loss) in losses.iter().enumerate() {
+            hp -= loss;
+            samples.push(sample(second as i64 + 1, hp, 0));
+        }
+
+        let time_to_kill = estimate_time_to_kill(&samples, TimeDelta::seconds(30)).unwrap();
+
+        assert!(time_to_kill.lower_bound < time_to_kill.estimate);
+        assert!(time_to_kill.upper_bound.unwrap() > time_to_kill.estimate);
+    }
+
+    #[test]
+    fn test_no_estimate_without_hp_loss() {
+        let samples: Vec<_> = (0..10).map(|second| sample(second, 1000, 0)).collect();
+
+        assert!(estimate_time_to_kill(&samples, TimeDelta::seconds(30)).is_none());
+        assert!(estimate_time_to_kill(&[], TimeDelta::seconds(30)).is_none());
+    }
+}
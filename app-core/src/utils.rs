@@ -1,21 +1,17 @@
-pub fn abbreviate_number(num: i64) -> (f64, char) {
-    let num= num as f64;
-
-    if num >= 1e12 {
-        return (num / 1e12, 't');
+/// Damage per second over `duration_ms`, 0 when no time has passed yet.
+pub fn dps(total_damage: i64, duration_ms: i64) -> i64 {
+    if duration_ms <= 0 {
+        return 0;
     }
 
-    if num >= 1e9 {
-        return (num / 1e9, 'b');
-    }
-    
-    if num >= 1e6 {
-        return (num / 1e6, 'm');
-    }
-    
-    if num >= 1e3 {
-        return (num / 1e3, 'k');
+    (total_damage as i128 * 1000 / duration_ms as i128) as i64
+}
+
+/// Share of `value` in `total` as a fraction, 0 when there is no total yet.
+pub fn percentage(value: i64, total: i64) -> f32 {
+    if total == 0 {
+        return 0.0;
     }
-    
-    (num, ' ')
-}
\ No newline at end of file
+
+    value as f32 / total as f32
+}
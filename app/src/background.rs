@@ -1,39 +1,55 @@
-use std::{error::Error, sync::Arc, time::Duration};
+use std::{error::Error, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Duration};
 
+use app_core::delta::FightUpdateEncoder;
+use chrono::Utc;
+use data::json::names::DataNames;
 use log::debug;
 use tokio::time::sleep;
 
-use crate::{fight_simulator::{EstherTemplate, FightSimulator}, misc::AppEventEmitter};
+use crate::{misc::AppEventEmitter, scenario::create_default_scenario};
 
-pub async fn run_background_work<E: AppEventEmitter>(event_emitter: Arc<E>) -> Result<(), Box<dyn Error + Send + Sync>> {
+/// Set by the meter when it (re)subscribes, the next fight update is then sent as a full snapshot.
+#[derive(Debug, Default)]
+pub struct FightUpdateSubscription {
+    snapshot_requested: AtomicBool
+}
+
+impl FightUpdateSubscription {
+    pub fn request_snapshot(&self) {
+        self.snapshot_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn take_snapshot_request(&self) -> bool {
+        self.snapshot_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Set when the datasets were reloaded or the locale changed, the fight picks up the new names before its next update.
+#[derive(Debug, Default)]
+pub struct NameRefresh {
+    refresh_requested: AtomicBool
+}
+
+impl NameRefresh {
+    pub fn request_refresh(&self) {
+        self.refresh_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn take_refresh_request(&self) -> bool {
+        self.refresh_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+pub async fn run_background_work<E: AppEventEmitter>(
+    event_emitter: Arc<E>,
+    subscription: Arc<FightUpdateSubscription>,
+    name_refresh: Arc<NameRefresh>) -> Result<(), Box<dyn Error + Send + Sync>> {
     
     debug!("run_background_work");
     // let event_emitter = event_emitter.as_ref();
 
-    let mut fight_simulator = FightSimulator::new();
-    let min_dmg = 100_000_000;
-    let max_dmg = 250_000_000;
-    fight_simulator.create_8_players(min_dmg, max_dmg)?;
-    fight_simulator.set_random_player_dead();
-    fight_simulator.create_boss(485000, "Red Doom Narkiel", 100_000_000_000, 180);
-    fight_simulator.configure_esther(EstherTemplate {
-        name: "Azena".into(),
-        icon: "azena.png".into(),
-        min_dmg: 2_000_000_000,
-        max_dmg: 4_000_000_000
-    });
-    fight_simulator.configure_esther(EstherTemplate {
-        name: "Avele".into(),
-        icon: "avele.png".into(),
-        min_dmg: 1_000_000_000,
-        max_dmg: 2_000_000_000
-    });
-    fight_simulator.configure_esther(EstherTemplate {
-        name: "Thar".into(),
-        icon: "thar.png".into(),
-        min_dmg: 500_000_000,
-        max_dmg: 1_000_000_000
-    });
+    let mut fight_simulator = create_default_scenario()?;
+    let mut encoder = FightUpdateEncoder::new();
 
     let interval = Duration::from_millis(500);
 
@@ -43,19 +59,22 @@ pub async fn run_background_work<E: AppEventEmitter>(event_emitter: Arc<E>) -> R
             break;
         }
 
-        fight_simulator.update_time();
-        fight_simulator.update_esther_gauge();
+        if name_refresh.take_refresh_request() {
+            fight_simulator.refresh_names(&DataNames::current());
+        }
 
-        fight_simulator.update_duration();
-        fight_simulator.perform_attacks_and_update_stats()?;
+        fight_simulator.tick(Utc::now())?;
 
-        fight_simulator.try_use_esther();
+        if subscription.take_snapshot_request() {
+            encoder.reset();
+        }
 
-        let app_event = fight_simulator.to_fight_update_event();
-        event_emitter.emit(app_event)?;
+        if let Some(fight_update) = fight_simulator.to_fight_update() {
+            event_emitter.emit(encoder.encode(fight_update))?;
+        }
 
         sleep(interval).await;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,94 @@
+use std::{env, error::Error, fs::File, io::{self, BufWriter, Write}};
+
+use bowl_of_ramen_lib::scenario::create_default_scenario;
+use chrono::{TimeDelta, Utc};
+
+const USAGE: &str = "Usage: simulate [--output <path>] [--encounter <path>] [--interval-ms <ms>] [--max-ticks <count>]";
+
+struct Args {
+    output: Option<String>,
+    encounter: Option<String>,
+    interval_ms: i64,
+    max_ticks: Option<u32>
+}
+
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut args = Args {
+        output: None,
+        encounter: None,
+        interval_ms: 500,
+        max_ticks: None
+    };
+
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("Missing value for {arg}\n{USAGE}"));
+
+        match arg.as_str() {
+            "--output" | "-o" => args.output = Some(value()?),
+            "--encounter" => args.encounter = Some(value()?),
+            "--interval-ms" => {
+                args.interval_ms = value()?.parse()?;
+
+                // Time has to advance for players to finish casting and the fight to end.
+                if args.interval_ms <= 0 {
+                    return Err(format!("--interval-ms must be positive\n{USAGE}").into());
+                }
+            }
+            "--max-ticks" => args.max_ticks = Some(value()?.parse()?),
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument: {arg}\n{USAGE}").into()),
+        }
+    }
+
+    Ok(args)
+}
+
+/// Runs the default scenario to completion without Tauri and writes every
+/// `FightUpdate` as a line of JSON and optionally the finished `Encounter` to a separate file,
+/// e.g. to generate web fixture data:
+///
+/// `cargo run --bin simulate -- --output ../web/public/fixtures/fight-updates.ndjson`
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args()?;
+
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    let mut fight_simulator = create_default_scenario()?;
+    let interval = TimeDelta::milliseconds(args.interval_ms);
+    let mut now = Utc::now();
+    let mut ticks = 0;
+
+    while !fight_simulator.has_ended() {
+
+        if args.max_ticks.is_some_and(|max_ticks| ticks >= max_ticks) {
+            break;
+        }
+
+        fight_simulator.tick(now)?;
+
+        let app_event = fight_simulator.to_fight_update_event();
+        serde_json::to_writer(&mut writer, &app_event)?;
+        writer.write_all(b"\n")?;
+
+        now += interval;
+        ticks += 1;
+    }
+
+    writer.flush()?;
+
+    if let Some(path) = &args.encounter {
+        let encounter = fight_simulator.to_encounter().ok_or("Encounter has not started")?;
+        serde_json::to_writer(BufWriter::new(File::create(path)?), &encounter)?;
+    }
+
+    Ok(())
+}
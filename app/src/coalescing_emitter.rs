@@ -0,0 +1,247 @@
+use std::{any::{Any, TypeId}, error::Error, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use app_core::models::AppEvent;
+use log::{debug, error, warn};
+use serde::Serialize;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::misc::AppEventEmitter;
+
+/// Metrics are logged once per this many frames.
+const METRICS_LOG_FRAMES: u64 = 300;
+
+#[derive(Debug, Clone)]
+pub struct CoalescingOptions {
+    pub frame_interval: Duration
+}
+
+impl CoalescingOptions {
+    pub fn from_frame_rate(frame_rate: u32) -> Self {
+        Self {
+            frame_interval: Duration::from_secs(1) / frame_rate.max(1)
+        }
+    }
+}
+
+/// Backpressure indicators, a growing `coalesced` or `overruns` count means events arrive
+/// faster than they can be emitted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EmitterMetrics {
+    pub received: u64,
+    pub emitted: u64,
+    /// Events merged into one still waiting for the next frame.
+    pub coalesced: u64,
+    pub failed: u64,
+    pub frames: u64,
+    /// Frames whose emission took longer than the frame interval.
+    pub overruns: u64,
+    pub max_pending_ms: u64,
+    pub max_frame_ms: u64
+}
+
+/// Event waiting for the next frame, type erased so events of any kind can be queued together.
+trait PendingEvent<E>: Send {
+    fn key(&self) -> (&'static str, TypeId);
+    fn queued_on(&self) -> Instant;
+    fn merge(&mut self, newer: Box<dyn Any + Send>);
+    fn emit(self: Box<Self>, emitter: &E) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+struct Pending<T> {
+    event: T,
+    queued_on: Instant
+}
+
+impl<E: AppEventEmitter, T: AppEvent + Send + 'static> PendingEvent<E> for Pending<T> {
+    fn key(&self) -> (&'static str, TypeId) {
+        (self.event.event_name(), TypeId::of::<T>())
+    }
+
+    fn queued_on(&self) -> Instant {
+        self.queued_on
+    }
+
+    fn merge(&mut self, newer: Box<dyn Any + Send>) {
+        if let Ok(newer) = newer.downcast::<T>() {
+            self.event.merge(*newer);
+        }
+    }
+
+    fn emit(self: Box<Self>, emitter: &E) -> Result<(), Box<dyn Error + Send + Sync>> {
+        emitter.emit(self.event)
+    }
+}
+
+struct CoalescingState<E> {
+    pending: Vec<Box<dyn PendingEvent<E>>>,
+    metrics: EmitterMetrics
+}
+
+/// Queues events and emits them through `inner` at most once per frame,
+/// events of the same kind arriving within a frame are merged with [`AppEvent::merge`].
+pub struct CoalescingEventEmitter<E> {
+    inner: E,
+    options: CoalescingOptions,
+    state: Mutex<CoalescingState<E>>
+}
+
+impl<E: AppEventEmitter> CoalescingEventEmitter<E> {
+    pub fn new(inner: E, options: CoalescingOptions) -> Self {
+        Self {
+            inner,
+            options,
+            state: Mutex::new(CoalescingState {
+                pending: vec![],
+                metrics: EmitterMetrics::default()
+            })
+        }
+    }
+
+    pub fn metrics(&self) -> EmitterMetrics {
+        self.state.lock().unwrap().metrics.clone()
+    }
+
+    /// Emits everything queued since the last frame.
+    pub fn flush(&self) {
+        let now = Instant::now();
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending);
+        let max_pending_ms = pending.iter()
+            .map(|event| (now - event.queued_on()).as_millis() as u64)
+            .max()
+            .unwrap_or_default();
+
+        let mut emitted = 0;
+        let mut failed = 0;
+
+        for event in pending {
+            match event.emit(&self.inner) {
+                Ok(_) => emitted += 1,
+                Err(err) => {
+                    error!("could not emit event: {:?}", err);
+                    failed += 1;
+                },
+            }
+        }
+
+        let frame_duration = now.elapsed();
+        let mut state = self.state.lock().unwrap();
+        let metrics = &mut state.metrics;
+        metrics.frames += 1;
+        metrics.emitted += emitted;
+        metrics.failed += failed;
+        metrics.max_pending_ms = metrics.max_pending_ms.max(max_pending_ms);
+        metrics.max_frame_ms = metrics.max_frame_ms.max(frame_duration.as_millis() as u64);
+
+        if frame_duration > self.options.frame_interval {
+            metrics.overruns += 1;
+            warn!("emitting took {:?}, longer than the frame interval of {:?}", frame_duration, self.options.frame_interval);
+        }
+
+        if metrics.frames % METRICS_LOG_FRAMES == 0 {
+            debug!("{:?}", metrics);
+        }
+    }
+
+    /// Flushes once per frame until the task is dropped.
+    pub async fn run(self: Arc<Self>) {
+        let mut frames = interval(self.options.frame_interval);
+        frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            frames.tick().await;
+            self.flush();
+        }
+    }
+}
+
+impl<E: AppEventEmitter> AppEventEmitter for CoalescingEventEmitter<E> {
+    fn emit(&self, event: impl AppEvent + Send + 'static) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let key = (event.event_name(), event.type_id());
+        let mut state = self.state.lock().unwrap();
+        state.metrics.received += 1;
+
+        if let Some(pending) = state.pending.iter_mut().find(|pending| pending.key() == key) {
+            pending.merge(Box::new(event));
+            state.metrics.coalesced += 1;
+            return Ok(());
+        }
+
+        state.pending.push(Box::new(Pending {
+            event,
+            queued_on: Instant::now()
+        }));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    struct Counter(u32);
+
+    impl AppEvent for Counter {
+        fn event_name(&self) -> &'static str {
+            "counter"
+        }
+
+        fn merge(&mut self, newer: Self) {
+            self.0 += newer.0;
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    struct Status(&'static str);
+
+    impl AppEvent for Status {
+        fn event_name(&self) -> &'static str {
+            "status"
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingEmitter {
+        emitted: Mutex<Vec<String>>
+    }
+
+    impl AppEventEmitter for RecordingEmitter {
+        fn emit(&self, event: impl AppEvent + Send + 'static) -> Result<(), Box<dyn Error + Send + Sync>> {
+            let json = serde_json::to_string(&event)?;
+            self.emitted.lock().unwrap().push(format!("{}:{}", event.event_name(), json));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_merged_until_the_next_frame() {
+        let emitter = CoalescingEventEmitter::new(RecordingEmitter::default(), CoalescingOptions::from_frame_rate(30));
+
+        emitter.emit(Counter(1)).unwrap();
+        emitter.emit(Status("started")).unwrap();
+        emitter.emit(Counter(2)).unwrap();
+        emitter.emit(Status("running")).unwrap();
+        emitter.emit(Counter(3)).unwrap();
+
+        assert!(emitter.inner.emitted.lock().unwrap().is_empty());
+
+        emitter.flush();
+        emitter.flush();
+
+        let emitted = emitter.inner.emitted.lock().unwrap().clone();
+        assert_eq!(emitted, vec!["counter:6".to_string(), "status:\"running\"".to_string()]);
+
+        let metrics = emitter.metrics();
+        assert_eq!(metrics.received, 5);
+        assert_eq!(metrics.coalesced, 3);
+        assert_eq!(metrics.emitted, 2);
+        assert_eq!(metrics.frames, 2);
+    }
+
+    #[test]
+    fn test_frame_interval_from_frame_rate() {
+        assert_eq!(CoalescingOptions::from_frame_rate(20).frame_interval, Duration::from_millis(50));
+        assert_eq!(CoalescingOptions::from_frame_rate(0).frame_interval, Duration::from_secs(1));
+    }
+}
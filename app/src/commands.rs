@@ -1,14 +1,33 @@
+use std::sync::Arc;
+
+use app_core::settings::Settings;
 use commands::load::{load_inner, LoadResult};
-use tauri::{command, generate_handler, ipc, AppHandle, Manager};
+use data::json::names::set_locale;
+use tauri::{command, generate_handler, ipc, AppHandle, Manager, State};
+
+use crate::background::{FightUpdateSubscription, NameRefresh};
 
+/// Switches the names to `locale`, the fight picks them up before its next update.
 #[command]
-pub fn load(app_handle: AppHandle, locale: String) -> LoadResult {
+pub fn load(
+    app_handle: AppHandle,
+    locale: String,
+    name_refresh: State<'_, Arc<NameRefresh>>,
+    settings: State<'_, Settings>) -> LoadResult {
     let package_info = app_handle.package_info();
     let version = package_info.version.to_string();
+    let locale = set_locale(&locale);
+    name_refresh.request_refresh();
 
-    load_inner(version)
+    load_inner(version, locale.into(), settings.inner().clone())
+}
+
+/// Called by the meter when it starts listening, the next fight update is a full snapshot.
+#[command]
+pub fn subscribe_fight_updates(subscription: State<'_, Arc<FightUpdateSubscription>>) {
+    subscription.request_snapshot();
 }
 
 pub fn generate_handlers() -> Box<dyn Fn(ipc::Invoke) -> bool + Send + Sync> {
-    Box::new(generate_handler![load])
+    Box::new(generate_handler![load, subscribe_fight_updates])
 }
@@ -0,0 +1,119 @@
+use std::{fs, path::{Path, PathBuf}, sync::Arc, time::{Duration, SystemTime}};
+
+use anyhow::Result;
+use app_core::models::DataReloaded;
+use data::json::overrides::{load_overrides, OVERRIDE_FILES};
+use log::{info, warn};
+use tokio::time::sleep;
+
+use crate::{background::NameRefresh, misc::AppEventEmitter};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Modification time and length of each override file, `None` for missing ones.
+type Fingerprint = Vec<Option<(SystemTime, u64)>>;
+
+/// Loads the override dumps in `dir` and reloads them whenever one is added, changed or removed,
+/// then has the fight pick up the new names and tells the meter with [`DataReloaded`].
+/// Invalid dumps are logged and leave the overrides loaded before in place until fixed, failing to tell
+/// the meter is logged as well, polling goes on either way.
+pub async fn watch_data_overrides<E: AppEventEmitter>(
+    dir: PathBuf,
+    event_emitter: Arc<E>,
+    name_refresh: Arc<NameRefresh>) {
+
+    let mut last_fingerprint: Fingerprint = vec![None; OVERRIDE_FILES.len()];
+
+    loop {
+        match poll_overrides(&dir, &mut last_fingerprint) {
+            Some(Ok(event)) => {
+                info!("loaded data overrides from {}: {:?}", dir.display(), event);
+                name_refresh.request_refresh();
+
+                if let Err(err) = event_emitter.emit(event) {
+                    warn!("could not emit data reload: {:?}", err);
+                }
+            },
+            Some(Err(err)) => {
+                warn!("could not load data overrides: {:#}", err);
+            },
+            None => {}
+        }
+
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Reloads the overrides when the files in `dir` changed since `last_fingerprint` was taken, `None` when they did not.
+/// The fingerprint moves on even when loading fails, so an invalid dump is reported once until changed again.
+fn poll_overrides(dir: &Path, last_fingerprint: &mut Fingerprint) -> Option<Result<DataReloaded>> {
+    let fingerprint = fingerprint(dir);
+
+    if fingerprint == *last_fingerprint {
+        return None;
+    }
+
+    *last_fingerprint = fingerprint;
+
+    Some(load_overrides(dir).map(|overrides| DataReloaded {
+        classes: overrides.classes.len(),
+        skills: overrides.skills.len(),
+        npcs: overrides.npcs.len(),
+        status_effects: overrides.status_effects.len()
+    }))
+}
+
+fn fingerprint(dir: &Path) -> Fingerprint {
+    OVERRIDE_FILES.iter()
+        .map(|name| {
+            let metadata = fs::metadata(dir.join(name)).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use data::json::{find_skill, overrides::current_overrides};
+
+    use super::*;
+
+    const SKILL_OVERRIDE: &str = r#"{ "999001": { "id": 999001, "name": "Patched Skill", "classid": 102, "icon": "patched.png", "grade": "normal" } }"#;
+    const SKILL_OVERRIDES: &str = r#"{
+        "999001": { "id": 999001, "name": "Patched Skill II", "classid": 102, "icon": "patched.png", "grade": "normal" },
+        "999002": { "id": 999002, "name": "Added Skill", "classid": 102, "icon": "added.png", "grade": "super" }
+    }"#;
+
+    fn skills(result: Option<Result<DataReloaded>>) -> usize {
+        result.expect("expected a reload").expect("expected valid overrides").skills
+    }
+
+    #[test]
+    fn test_poll_reloads_only_on_changes_and_keeps_the_last_valid_overrides() {
+        let dir = env::temp_dir().join(format!("app-data-overrides-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut fingerprint: Fingerprint = vec![None; OVERRIDE_FILES.len()];
+
+        assert!(poll_overrides(&dir, &mut fingerprint).is_none());
+
+        fs::write(dir.join("Skill.json"), SKILL_OVERRIDE).unwrap();
+        assert_eq!(skills(poll_overrides(&dir, &mut fingerprint)), 1);
+        assert!(poll_overrides(&dir, &mut fingerprint).is_none());
+
+        fs::write(dir.join("Skill.json"), SKILL_OVERRIDES).unwrap();
+        assert_eq!(skills(poll_overrides(&dir, &mut fingerprint)), 2);
+        assert_eq!(find_skill(999001).unwrap().name.as_deref(), Some("Patched Skill II"));
+
+        fs::write(dir.join("Skill.json"), "{ not json").unwrap();
+        assert!(poll_overrides(&dir, &mut fingerprint).unwrap().is_err());
+        assert_eq!(current_overrides().skills.len(), 2);
+        assert!(poll_overrides(&dir, &mut fingerprint).is_none());
+
+        fs::remove_file(dir.join("Skill.json")).unwrap();
+        assert_eq!(skills(poll_overrides(&dir, &mut fingerprint)), 0);
+        assert!(find_skill(999001).is_none());
+    }
+}
@@ -1,13 +1,34 @@
 use anyhow::Error;
-use app_core::models::*;
-use std::{cmp::max, collections::{HashMap, HashSet}, ops::Deref};
+use app_core::{aggregator::Aggregator, events::*, models::*, names::NameLookup};
+use std::{cmp::min, collections::{HashMap, HashSet}};
 
 use chrono::{DateTime, TimeDelta, Utc};
-use data::json::{models::{Class, RawSkill, SkillGrade}, CLASS_MAP, SKILL_MAP};
+use data::json::{find_npc, find_status_effect, models::{Class, RawSkill, SkillGrade, StatusEffectCategory}, names::DataNames, CLASS_MAP, SKILL_MAP};
 use log::{debug, info};
 use rand::{seq::SliceRandom, Rng};
 use serde::Serialize;
 
+/// Made up like [`BOSS_ATTACK_SKILL_ID`], both at the top of the id range so their stats are not merged
+/// with a skill of the dataset or with each other.
+const HYPER_AWAKENING_SKILL_ID: SkillId = SkillId::MAX;
+
+/// Skill of the boss hits when no boss skill is configured.
+const BOSS_ATTACK_SKILL_ID: SkillId = SkillId::MAX - 1;
+
+/// Boss hp percentages at which the next phase starts and the boss puts up a shield.
+const PHASE_HP_PERCENTAGES: [f32; 2] = [66.0, 33.0];
+
+/// Party shield cast by supports whenever it comes off cooldown.
+const SUPPORT_SHIELD_STATUS_EFFECT_ID: StatusEffectId = 211601;
+const SUPPORT_SHIELD_HP: i64 = 50_000;
+const SUPPORT_SHIELD_COOLDOWN_SECONDS: i64 = 20;
+
+/// Dead players get resurrected after this many seconds.
+const RESURRECTION_DELAY_SECONDS: i64 = 10;
+
+/// Chance that a cast of a skill able to counter counts as a counter, there is no boss counter window.
+const COUNTER_CHANCE: f32 = 0.3;
+
 const HIT_OPTIONS: [HitOption; 3] = [HitOption::BackAttack, HitOption::FlankAttack, HitOption::FrontalAttack];
 
 pub fn random_hit_option() -> HitOption {
@@ -18,6 +39,10 @@ pub fn random_hit_option() -> HitOption {
 #[derive(Debug)]
 pub struct AttackResult<'a> {
     pub skill: &'a Skill,
+    pub is_hyper_awakening: bool,
+    pub is_counter: bool,
+    pub stagger: i64,
+    pub destruction: i64,
     pub has_fight_ended: bool,
     pub hit_flag: HitFlag,
     pub hit_option: HitOption,
@@ -26,24 +51,91 @@ pub struct AttackResult<'a> {
 
 #[derive(Debug)]
 pub struct PlayerWithTemplate {
-    pub entity: Player,
-    pub template: PlayerTemplate
+    pub id: EntityId,
+    pub template: PlayerTemplate,
+    pub rotation: RotationState,
+    pub shield_ready_on: DateTime<Utc>
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct PlayerTemplate {
-    skills: Vec<Skill>,
+    skills: Vec<SimulatedSkill>,
     crit_rate: f32,
     min_dmg: i64,
-    max_dmg: i64
+    max_dmg: i64,
+    shield_hp: i64,
+    is_support: bool
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedSkill {
+    pub skill: Skill,
+    pub grade: SkillGrade,
+    pub cooldown: TimeDelta,
+    pub cast_time: TimeDelta,
+    pub damage_coefficient: f64,
+    pub is_hyper_awakening: bool,
+    pub can_counter: bool,
+    pub stagger: i64,
+    pub destruction: i64
+}
+
+/// Tracks when each skill of the player's rotation comes off cooldown
+/// and until when the player is busy casting.
+#[derive(Debug, Clone, Default)]
+pub struct RotationState {
+    skill_ready_on: Vec<DateTime<Utc>>,
+    casting_until: Option<DateTime<Utc>>
+}
+
+impl RotationState {
+    pub fn new(skills_count: usize, now: DateTime<Utc>) -> Self {
+        Self {
+            skill_ready_on: vec![now; skills_count],
+            casting_until: None
+        }
+    }
+
+    /// Returns index of the first skill in priority order which can be cast at given time.
+    pub fn next_skill_index(&self, now: DateTime<Utc>) -> Option<usize> {
+        if self.casting_until.filter(|casting_until| *casting_until > now).is_some() {
+            return None;
+        }
+
+        self.skill_ready_on.iter().position(|ready_on| *ready_on <= now)
+    }
+
+    pub fn start_cast(&mut self, index: usize, skill: &SimulatedSkill, now: DateTime<Utc>) {
+        self.skill_ready_on[index] = now + skill.cooldown;
+        self.casting_until = Some(now + skill.cast_time);
+    }
 }
 
 #[derive(Debug)]
 pub struct EstherWithTemplate {
-    pub entity: Esther,
+    pub id: EntityId,
     pub template: EstherTemplate
 }
 
+/// Boss skill named from the skill dataset, `name` and `icon` stand in for a skill missing from it.
+#[derive(Debug, Clone, Serialize)]
+pub struct BossSkillTemplate {
+    pub id: SkillId,
+    pub name: String,
+    pub icon: String,
+    pub min_dmg: i64,
+    pub max_dmg: i64
+}
+
+/// Synergy from the status effect dataset applied by supports whenever it comes off cooldown,
+/// debuffs are put on the boss and buffs on the party.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportBuffTemplate {
+    pub status_effect_id: StatusEffectId,
+    pub duration_seconds: i64,
+    pub cooldown_seconds: i64
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct EstherTemplate {
     pub name: String,
@@ -65,12 +157,15 @@ pub struct FightSimulator<'a> {
     esther_gauge: f32,
     updated_esther_gauge_on: Option<DateTime<Utc>>,
     last_used_esther_on: Option<DateTime<Utc>>,
-    boss: Option<Boss>,
+    boss_id: Option<EntityId>,
+    boss_skills: Vec<BossSkillTemplate>,
+    support_buffs: Vec<SupportBuffTemplate>,
+    support_buffs_ready_on: HashMap<(EntityId, StatusEffectId), DateTime<Utc>>,
+    status_effect_expirations: Vec<(DateTime<Utc>, StatusEffectEvent)>,
+    boss_phase: u32,
     has_fight_ended: bool,
     current_time: DateTime<Utc>,
-    fight_started_on: Option<DateTime<Utc>>,
-    duration: TimeDelta,
-    stats: EncounterStats
+    aggregator: Aggregator
 }
 
 impl<'a> FightSimulator<'a> {
@@ -87,16 +182,16 @@ impl<'a> FightSimulator<'a> {
 
         for skill in SKILL_MAP.values() {
 
-            if skill.grade != SkillGrade::Normal {
+            if skill.grade == SkillGrade::Unknown {
                 continue;
             }
 
-            let name = skill.name
+            let name = skill.name.as_deref()
                 .filter(|name| *name != "Weapon Attack"
                     && *name != "Hand Attack"
                     && *name !=  "Stand Up");
 
-            match (skill.class_id, name, skill.icon) {
+            match (skill.class_id, name, skill.icon.as_deref()) {
                 (Some(class_id), Some(_), Some(_)) => {
                     let entry: &mut Vec<&'a RawSkill> = skills_by_class.entry(class_id).or_default();
                     entry.push(skill);
@@ -112,6 +207,7 @@ impl<'a> FightSimulator<'a> {
             dps_classes,
             sup_classes,
             esther_gauge: 2.0,
+            boss_phase: 1,
             ..Default::default()
         }
     }
@@ -135,10 +231,12 @@ impl<'a> FightSimulator<'a> {
             let class_id = class.id;
             let mut class_skills = self.skills_by_class.get(&class_id).cloned().unwrap_or_default();
             let mut template =  PlayerTemplate {
-                skills: Self::get_random_skills_for_class(&mut class_skills),
+                skills: Self::get_random_rotation_for_class(&mut class_skills),
                 crit_rate: 0.1,
                 min_dmg: 10_000,
                 max_dmg: 100_000,
+                shield_hp: SUPPORT_SHIELD_HP,
+                is_support: class.is_support
             };
 
             if template.skills.is_empty() {
@@ -149,21 +247,26 @@ impl<'a> FightSimulator<'a> {
                 template.crit_rate = Self::get_random_f32_value(0.5, 1.0);
                 template.min_dmg = min_dmg;
                 template.max_dmg = max_dmg;
+                template.shield_hp = 0;
             };
 
             let player = Player {
                 id,
                 name,
                 class_color: self.class_color_map.get(&class.id).cloned().unwrap_or_else(|| "#FFFFFF".to_string()),
-                class_name: class.name.to_string(),
+                class_name: DataNames::current().class_name(class.id).unwrap_or_else(|| class.name.to_string()),
                 class_id: class.id,
                 stats: PlayerStats::default(),
                 death_log: DeathLog::default(),
                 created_on: now,
             };
 
+            self.aggregator.add_player(player);
+
             let player_with_template = PlayerWithTemplate {
-                entity: player,
+                id,
+                rotation: RotationState::new(template.skills.len(), now),
+                shield_ready_on: now,
                 template
             };
 
@@ -178,35 +281,74 @@ impl<'a> FightSimulator<'a> {
     pub fn set_random_player_dead(&mut self) {
         let now = Utc::now();
         let index = Self::get_random_index(self.players.len());
+        let player = &self.players[index];
 
-        let player = self.players.get_mut(index).unwrap();
-        let death_log = &mut player.entity.death_log;
-        death_log.is_dead = true;
-        death_log.recorded_on = Some(now);
-        death_log.duration = Some(now - now);
+        self.aggregator.process(EncounterEvent::Death(DeathEvent {
+            target_id: player.id,
+            recorded_on: now
+        }));
     }
 
     pub fn configure_esther(&mut self, template: EstherTemplate) {
+        let id = self.get_random_unique_entity_id();
         let esther = Esther {
-            id: self.get_random_unique_entity_id(),
+            id,
             name: template.name.clone(),
             icon: template.icon.clone(),
             stats: EstherStats::default(),
         };
 
+        self.aggregator.add_esther(esther);
+
         let esther = EstherWithTemplate {
-            entity: esther,
+            id,
             template
         };
 
         self.esthers.push(esther);
     }
 
+    pub fn configure_support_buff(&mut self, template: SupportBuffTemplate) -> Result<(), Error> {
+        let status_effect = find_status_effect(template.status_effect_id)
+            .ok_or_else(|| anyhow::format_err!("Could not find status effect: {}", template.status_effect_id))?;
+
+        if status_effect.is_party_synergy {
+            self.aggregator.track_status_effect(TrackedStatusEffect {
+                id: status_effect.id,
+                name: DataNames::current().status_effect_name(status_effect.id).unwrap_or_else(|| status_effect.name.into())
+            });
+        }
+
+        self.support_buffs.push(template);
+
+        Ok(())
+    }
+
     pub fn update_time(&mut self) {
-        let now = Utc::now();
+        self.set_time(Utc::now());
+    }
+
+    pub fn set_time(&mut self, now: DateTime<Utc>) {
         self.current_time = now;
     }
 
+    /// Advances the fight to given time: charges esther gauge, lets players and boss attack
+    /// and uses an esther when the gauge is full.
+    pub fn tick(&mut self, now: DateTime<Utc>) -> Result<(), Error> {
+        self.set_time(now);
+        self.update_esther_gauge();
+        self.try_resurrect_players();
+        self.expire_status_effects();
+        self.try_apply_shields();
+        self.try_apply_support_buffs();
+        self.perform_attacks()?;
+        self.try_transition_boss_phase();
+        self.try_use_esther();
+        self.aggregator.update(now);
+
+        Ok(())
+    }
+
     pub fn update_esther_gauge(&mut self) {
         let now = self.current_time;
 
@@ -231,6 +373,10 @@ impl<'a> FightSimulator<'a> {
             return;
         }
 
+        let Some(player_id) = self.get_random_alive_player_id() else {
+            return;
+        };
+
         let now = self.current_time;
         self.last_used_esther_on.get_or_insert(now);
         self.esther_gauge -= 3.0;
@@ -238,10 +384,12 @@ impl<'a> FightSimulator<'a> {
         let esther = Self::get_random_esther(&mut self.esthers);
         let esther_damage = Self::get_random_value(esther.template.min_dmg, esther.template.max_dmg);
 
-        self.stats.total_esther_damage += esther_damage;
-        let stats = &mut esther.entity.stats;
-        stats.total_damage += esther_damage;
-        stats.damage_percentage  = stats.total_damage as f32 / self.stats.total_esther_damage as f32;
+        self.aggregator.process(EncounterEvent::Esther(EstherEvent {
+            source_id: player_id,
+            esther_id: esther.id,
+            damage: esther_damage,
+            recorded_on: now
+        }));
     }
 
     pub fn create_player(&mut self) {
@@ -252,172 +400,268 @@ impl<'a> FightSimulator<'a> {
         self.has_fight_ended
     }
 
-    pub fn update_duration(&mut self) {
-        let now = self.current_time;
-        let fight_started_on = self.fight_started_on.get_or_insert_with(|| now);
-        
-        self.duration = now - *fight_started_on;
-    }
-
-    pub fn perform_attacks_and_update_stats(&mut self) -> Result<(), Error> {
-        
-        let duration_seconds = self.duration.num_seconds();
+    pub fn perform_attacks(&mut self) -> Result<(), Error> {
         let now = self.current_time;
+        let boss_id = self.boss_id.ok_or_else(|| anyhow::anyhow!("Boss unset"))?;
 
-        let boss = self.boss.as_mut().ok_or_else(|| anyhow::anyhow!("Boss unset"))?;
-        let mut players_who_performed_attack = HashSet::new();
-        let (start, end) = Self::generate_random_range(self.players.len() as u32);
-        let players_count = self.players.len();
-
-        for _ in start..end {
-            let mut player_index = Self::get_random_index(players_count);
-            let mut player = &mut self.players[player_index];
+        for player in self.players.iter_mut() {
+            let is_dead = self.aggregator.player(player.id)
+                .map(|entity| entity.death_log.is_dead)
+                .unwrap_or_default();
 
-            while players_who_performed_attack.contains(&player_index) || player.entity.death_log.is_dead {
-                player_index = Self::get_random_index(players_count);
-                player = &mut self.players[player_index]
+            if is_dead {
+                continue;
             }
 
-            players_who_performed_attack.insert(player_index);
-            
-            let current_boss_hp = boss.stats.hp;
-            let attack_result = Self::perform_attack(current_boss_hp, &player.template);
-            self.stats.total_player_damage += attack_result.damage;
-            self.stats.top_damage = max(attack_result.damage, self.stats.top_damage);
+            let skill_index = match player.rotation.next_skill_index(now) {
+                Some(skill_index) => skill_index,
+                None => continue,
+            };
+
+            let skill = &player.template.skills[skill_index];
+            player.rotation.start_cast(skill_index, skill, now);
+
+            self.aggregator.process(EncounterEvent::SkillCast(SkillCastEvent {
+                source_id: player.id,
+                skill: skill.skill.clone(),
+                recorded_on: now
+            }));
+
+            let (boss_hp, boss_shield_hp) = self.aggregator.boss()
+                .map(|boss| (boss.stats.hp, boss.stats.shield_hp))
+                .unwrap_or_default();
+            let attack_result = Self::perform_attack(boss_hp + boss_shield_hp, &player.template, skill);
+            let shield_damage = min(boss_shield_hp, attack_result.damage);
+
+            self.aggregator.process(EncounterEvent::Damage(DamageEvent {
+                source_id: player.id,
+                target_id: boss_id,
+                skill: attack_result.skill.clone(),
+                damage: attack_result.damage,
+                shield_damage,
+                hit_flag: attack_result.hit_flag,
+                hit_option: attack_result.hit_option,
+                is_hyper_awakening: attack_result.is_hyper_awakening,
+                is_counter: attack_result.is_counter,
+                stagger: attack_result.stagger,
+                destruction: attack_result.destruction,
+                recorded_on: now
+            }));
 
             if attack_result.has_fight_ended {
                 self.has_fight_ended = true;
-                let stats = &mut boss.stats;
-                Self::update_boss(stats, &attack_result, now);
-                break;
+                return Ok(());
             }
-
-            Self::update_player(
-                &mut player.entity,
-                &attack_result,
-                self.stats.total_player_damage,
-                now);
-    
-            let stats = &mut boss.stats;
-            Self::update_boss(stats, &attack_result, now);
         }
 
-        let boss_damage = Self::perform_boss_attack(boss);
-        self.stats.total_taken_damage += boss_damage;
+        self.perform_boss_attack(boss_id);
 
-        let player = Self::get_random_alive_player(&mut self.players);
-        Self::update_damage_taken(player, boss_damage, self.stats.total_taken_damage);
+        Ok(())
+    }
 
-        self.stats.party_dps = Dps::new(self.stats.total_player_damage, duration_seconds);
-        let time_to_kill = boss.stats.hp / self.stats.party_dps.raw;
-        self.stats.time_to_kill_mmss = Self::seconds_to_mm_ss(time_to_kill);
+    /// Supports shield every alive party member whenever their shield is ready.
+    pub fn try_apply_shields(&mut self) {
+        let now = self.current_time;
+        let alive_player_ids: Vec<EntityId> = self.aggregator.players()
+            .filter(|player| !player.death_log.is_dead)
+            .map(|player| player.id)
+            .collect();
 
-        self.recalculate_all_stats(now, duration_seconds);
+        for player in self.players.iter_mut() {
+            if player.template.shield_hp == 0
+                || player.shield_ready_on > now
+                || !alive_player_ids.contains(&player.id) {
+                continue;
+            }
 
-        Ok(())
+            player.shield_ready_on = now + TimeDelta::seconds(SUPPORT_SHIELD_COOLDOWN_SECONDS);
+
+            for target_id in alive_player_ids.iter() {
+                self.aggregator.process(EncounterEvent::StatusEffect(StatusEffectEvent {
+                    source_id: player.id,
+                    target_id: *target_id,
+                    status_effect_id: SUPPORT_SHIELD_STATUS_EFFECT_ID,
+                    kind: StatusEffectEventKind::Applied,
+                    shield_hp: player.template.shield_hp,
+                    recorded_on: now
+                }));
+            }
+        }
     }
 
-    fn perform_boss_attack(boss: &mut Boss) -> i64 {
-        let boss_damage = Self::get_random_value(10_000, 20_000);
-        boss.stats.total_damage_dealt += boss_damage;
+    pub fn try_apply_support_buffs(&mut self) {
+        let now = self.current_time;
+        let Some(boss_id) = self.boss_id else {
+            return;
+        };
 
-        boss_damage 
-    }
+        let alive_player_ids: Vec<EntityId> = self.aggregator.players()
+            .filter(|player| !player.death_log.is_dead)
+            .map(|player| player.id)
+            .collect();
 
-    fn update_damage_taken(player: &mut Player, boss_damage: i64, total_taken_damage: i64, ) {
-        player.stats.damage_taken += boss_damage;
-        player.stats.damage_taken_percentage = player.stats.damage_taken as f32 / total_taken_damage as f32;
-    }
+        for player in self.players.iter().filter(|player| player.template.is_support) {
+            if !alive_player_ids.contains(&player.id) {
+                continue;
+            }
 
-    fn seconds_to_mm_ss(seconds: i64) -> String {
-        let minutes = seconds / 60;
+            for buff in self.support_buffs.iter() {
+                let ready_on = self.support_buffs_ready_on.entry((player.id, buff.status_effect_id)).or_insert(now);
 
-        if minutes > 60 {
-            return "∞".into();
-        }
+                if *ready_on > now {
+                    continue;
+                }
 
-        let remaining_seconds = seconds % 60;
-        format!("{:02}:{:02}", minutes, remaining_seconds)
-    }
+                *ready_on = now + TimeDelta::seconds(buff.cooldown_seconds);
 
-    fn recalculate_all_stats(&mut self, updated_on: DateTime<Utc>, duration_seconds: i64) {
-        for player in self.players.iter_mut() {
-            let death_log = &mut player.entity.death_log;
-            
-            if death_log.is_dead {
-                let duration = death_log.recorded_on.map(|recorded_on| updated_on - recorded_on);
-                death_log.duration = duration;
+                let is_debuff = find_status_effect(buff.status_effect_id)
+                    .is_some_and(|status_effect| status_effect.category == StatusEffectCategory::Debuff);
+
+                let target_ids = if is_debuff {
+                    vec![boss_id]
+                }
+                else {
+                    alive_player_ids.clone()
+                };
+
+                for target_id in target_ids {
+                    let event = StatusEffectEvent {
+                        source_id: player.id,
+                        target_id,
+                        status_effect_id: buff.status_effect_id,
+                        kind: StatusEffectEventKind::Applied,
+                        shield_hp: 0,
+                        recorded_on: now
+                    };
+
+                    self.status_effect_expirations.retain(|(_, pending)| pending.source_id != event.source_id
+                        || pending.target_id != event.target_id
+                        || pending.status_effect_id != event.status_effect_id);
+                    self.status_effect_expirations.push((now + TimeDelta::seconds(buff.duration_seconds), StatusEffectEvent {
+                        kind: StatusEffectEventKind::Removed,
+                        ..event.clone()
+                    }));
+                    self.aggregator.process(EncounterEvent::StatusEffect(event));
+                }
             }
-            
-            let stats = &mut player.entity.stats;
-            stats.dps = Dps::new(stats.total_damage, duration_seconds);
         }
     }
 
-    fn update_player(
-        player: &mut Player,
-        attack_result: &AttackResult,
-        total_damage: i64,
-        updated_on: DateTime<Utc>) {
-        let damage = attack_result.damage;
-        let stats = &mut player.stats;
+    pub fn try_resurrect_players(&mut self) {
+        let now = self.current_time;
+        let resurrect_on = now - TimeDelta::seconds(RESURRECTION_DELAY_SECONDS);
+        let dead_player_ids: Vec<EntityId> = self.aggregator.players()
+            .filter(|player| player.death_log.is_dead
+                && player.death_log.recorded_on.is_some_and(|recorded_on| recorded_on <= resurrect_on))
+            .map(|player| player.id)
+            .collect();
 
-        stats.top_damage = max(attack_result.damage, stats.top_damage);
-        stats.total_damage += attack_result.damage;
-        
-        stats.skills.hit_count += 1;
-        stats.crit_rate = stats.skills.crit_count as f32 / stats.skills.hit_count as f32;
-        let skill_id = attack_result.skill.id;
-        let skill_stat = stats.skills.skill.entry(skill_id.to_string()).or_insert_with(|| {
-            let mut skill_stat = PlayerSkillStats::default();
+        for target_id in dead_player_ids {
+            self.aggregator.process(EncounterEvent::Resurrect(ResurrectEvent {
+                target_id,
+                recorded_on: now
+            }));
+        }
+    }
 
-            skill_stat.id = skill_id;
-            skill_stat.name = attack_result.skill.name.clone();
-            skill_stat.icon = attack_result.skill.icon.clone();
+    /// Removes support buffs whose duration ran out.
+    pub fn expire_status_effects(&mut self) {
+        let now = self.current_time;
+        let (expired, pending) = std::mem::take(&mut self.status_effect_expirations)
+            .into_iter()
+            .partition(|(expires_on, _)| *expires_on <= now);
+        self.status_effect_expirations = pending;
+
+        for (expires_on, event) in expired {
+            self.aggregator.process(EncounterEvent::StatusEffect(StatusEffectEvent {
+                recorded_on: expires_on,
+                ..event
+            }));
+        }
+    }
 
-            skill_stat
-        });
+    pub fn try_transition_boss_phase(&mut self) {
+        let Some(boss) = self.aggregator.boss() else {
+            return;
+        };
+
+        let Some(threshold) = PHASE_HP_PERCENTAGES.get(self.boss_phase as usize - 1) else {
+            return;
+        };
 
-        if attack_result.hit_flag == HitFlag::Critical {
-            stats.crit_damage += damage;
-            stats.skills.crit_count += 1;
-            
-            skill_stat.crit_count += 1;
-            skill_stat.crit_damage += damage;
+        if boss.stats.hp == 0 || boss.stats.hp_percentage > *threshold {
+            return;
         }
 
-        skill_stat.total_damage += damage;
-        skill_stat.hit_count += 1;
+        let now = self.current_time;
+        let shield_hp = boss.stats.max_hp / 50;
+        self.boss_phase += 1;
 
-        match attack_result.hit_option {
-            HitOption::None => stats.non_positional_attacks_total_damage += damage,
-            HitOption::BackAttack => stats.back_attacks_total_damage += damage,
-            HitOption::FrontalAttack => stats.front_attacks_total_damage += damage,
-            HitOption::FlankAttack => stats.non_positional_attacks_total_damage += damage,
-            HitOption::Max => stats.non_positional_attacks_total_damage += damage,
-        }
+        self.aggregator.process(EncounterEvent::PhaseTransition(PhaseTransitionEvent {
+            phase: self.boss_phase,
+            recorded_on: now
+        }));
+        self.aggregator.process(EncounterEvent::BossShield(BossShieldEvent {
+            shield_hp,
+            recorded_on: now
+        }));
+    }
 
-        stats.damage_percentage = stats.total_damage as f32 / total_damage as f32;
-        stats.front_attacks_damage_percentage = stats.front_attacks_total_damage as f32 / stats.total_damage as f32;
-        stats.back_attacks_damage_percentage = stats.back_attacks_total_damage as f32 / stats.total_damage as f32;
-        stats.non_positional_attacks_damage_percentage = stats.non_positional_attacks_total_damage as f32 / stats.total_damage as f32;
-        stats.updated_on = updated_on;
-        // info!("{:?}", stats);
+    pub fn configure_boss_skill(&mut self, template: BossSkillTemplate) {
+        self.boss_skills.push(template);
     }
 
-    fn perform_attack<'b>(current_boss_hp: i64, template: &'b PlayerTemplate) -> AttackResult<'b> {
-        let mut has_fight_ended = false;
+    /// Hits a random alive player, nobody when the whole party is dead.
+    fn perform_boss_attack(&mut self, boss_id: EntityId) {
+        let Some(player_id) = self.get_random_alive_player_id() else {
+            return;
+        };
 
-        let mut damage = if current_boss_hp < template.max_dmg {
-            has_fight_ended = true;
-            current_boss_hp
-        }
-        else
-        {
-            Self::get_random_value(template.min_dmg, template.max_dmg)
+        let (skill, boss_damage) = match self.boss_skills.choose(&mut rand::thread_rng()) {
+            Some(template) => {
+                let skill = Self::boss_skill(template.id, &template.name, &template.icon);
+
+                (skill, Self::get_random_value(template.min_dmg, template.max_dmg))
+            },
+            None => (Self::boss_skill(BOSS_ATTACK_SKILL_ID, "Attack", ""), Self::get_random_value(10_000, 20_000))
         };
 
-        let skill = Self::get_random_item(&template.skills);
+        let shield_damage = min(self.aggregator.shield_hp(player_id), boss_damage);
+
+        self.aggregator.process(EncounterEvent::Damage(DamageEvent {
+            source_id: boss_id,
+            target_id: player_id,
+            skill,
+            damage: boss_damage - shield_damage,
+            shield_damage,
+            hit_flag: HitFlag::Normal,
+            hit_option: HitOption::None,
+            is_hyper_awakening: false,
+            is_counter: false,
+            stagger: 0,
+            destruction: 0,
+            recorded_on: self.current_time
+        }));
+    }
+
+    /// Skill from the skill dataset in the current locale, with `name` and `icon` when it is missing from it.
+    fn boss_skill(id: SkillId, name: &str, icon: &str) -> Skill {
+        DataNames::current().skill(id).unwrap_or_else(|| Skill {
+            id,
+            name: name.into(),
+            icon: icon.into()
+        })
+    }
+
+    /// Hit on the boss, ends the fight when it takes the last of `remaining_boss_hp`, shield included.
+    fn perform_attack<'b>(
+        remaining_boss_hp: i64,
+        template: &PlayerTemplate,
+        skill: &'b SimulatedSkill) -> AttackResult<'b> {
+        let mut has_fight_ended = false;
+
+        let base_damage = Self::get_random_value(template.min_dmg, template.max_dmg);
+        let mut damage = (base_damage as f64 * skill.damage_coefficient) as i64;
 
         let mut hit_flag = HitFlag::Normal;
         let hit_option = random_hit_option();
@@ -427,13 +671,19 @@ impl<'a> FightSimulator<'a> {
             hit_flag = HitFlag::Critical;
         }
 
-        if current_boss_hp < damage {
+        if remaining_boss_hp <= damage {
             has_fight_ended = true;
-            damage = current_boss_hp;
+            damage = remaining_boss_hp;
         };
 
+        let is_counter = skill.can_counter && Self::get_random_f32_value(0.0, 1.0) <= COUNTER_CHANCE;
+
         let result = AttackResult {
-            skill,
+            skill: &skill.skill,
+            is_hyper_awakening: skill.is_hyper_awakening,
+            is_counter,
+            stagger: skill.stagger,
+            destruction: skill.destruction,
             has_fight_ended,
             hit_flag,
             hit_option,
@@ -443,13 +693,19 @@ impl<'a> FightSimulator<'a> {
         result
     }
 
-    pub fn create_boss(&mut self, npc_id: NpcId, name: &str, max_hp: i64, hp_bars: i64) {
+    /// Creates the boss from the npc dataset, its hp bars and raid gate come from there.
+    pub fn create_boss(&mut self, npc_id: NpcId, max_hp: i64) -> Result<(), Error> {
+        let npc = find_npc(npc_id).ok_or_else(|| anyhow::format_err!("Could not find npc: {}", npc_id))?;
         let now = Utc::now();
+        let id = self.get_random_unique_entity_id();
+        let hp_bars = npc.hp_bars;
 
         let boss = Boss {
-            id: self.get_random_unique_entity_id(),
+            id,
             npc_id,
-            name: name.into(),
+            name: DataNames::current().npc_name(npc_id).unwrap_or_else(|| npc.name.to_string()),
+            raid_gate: Some(npc.raid_gate()),
+            image_key: Some(npc.image_key.into()),
             stats: BossStats {
                 max_hp: max_hp,
                 hp: max_hp,
@@ -463,77 +719,131 @@ impl<'a> FightSimulator<'a> {
             created_on: now
         };
 
-        self.boss = Some(boss);
+        self.boss_id = Some(id);
+        self.aggregator.set_boss(boss);
+
+        Ok(())
     }
     
-    fn get_random_skills_for_class(class_skills: &mut Vec<&RawSkill>) -> Vec<Skill> {
+    /// Picks the awakening and up to 7 other skills of the class and orders them
+    /// by priority: hyper awakening and awakening first, then skills with the highest damage per second of cooldown.
+    fn get_random_rotation_for_class(class_skills: &mut Vec<&RawSkill>) -> Vec<SimulatedSkill> {
         let mut rng = rand::thread_rng();
         class_skills.shuffle(&mut rng);
 
-        let random_8: Vec<_> = class_skills.iter().take(8).cloned().collect();
-
-        let mut skills = vec![];
+        let awakening = class_skills.iter()
+            .find(|skill| skill.grade == SkillGrade::Awakening)
+            .cloned();
+        let others = class_skills.iter()
+            .filter(|skill| skill.grade != SkillGrade::Awakening)
+            .take(7)
+            .cloned();
+
+        let mut skills: Vec<SimulatedSkill> = awakening.into_iter()
+            .chain(others)
+            .map(Self::to_simulated_skill)
+            .collect();
+        skills.push(Self::hyper_awakening_skill());
 
-        for raw_skill in random_8 {
-            let skill = Skill {
-                id: raw_skill.id,
-                icon: raw_skill.icon.unwrap().to_string(),
-                name: raw_skill.name.unwrap().to_string()
-            };
+        skills.sort_by(|a, b| {
+            let a_priority = a.damage_coefficient / a.cooldown.num_milliseconds() as f64;
+            let b_priority = b.damage_coefficient / b.cooldown.num_milliseconds() as f64;
 
-            skills.push(skill);
-        }
+            b.is_hyper_awakening.cmp(&a.is_hyper_awakening)
+                .then((b.grade == SkillGrade::Awakening).cmp(&(a.grade == SkillGrade::Awakening)))
+                .then(b_priority.total_cmp(&a_priority))
+        });
 
         skills
     }
 
-    fn update_boss(stats: &mut BossStats, attack_result: &AttackResult, updated_on: DateTime<Utc>) {
+    fn to_simulated_skill(raw_skill: &RawSkill) -> SimulatedSkill {
+        let skill = DataNames::current().skill(raw_skill.id).unwrap_or_else(|| Skill {
+            id: raw_skill.id,
+            icon: raw_skill.icon.as_deref().unwrap().to_string(),
+            name: raw_skill.name.as_deref().unwrap().to_string()
+        });
 
-        stats.hp -= attack_result.damage;
-        stats.hp_percentage = (stats.hp as f32) / (stats.max_hp as f32) * 100.0;
-        stats.hp_bars = (stats.hp as f32 / stats.hp_per_bar).floor() as i64;
-        stats.damage_taken = attack_result.damage;
-        stats.updated_on = updated_on;
-    }
+        let (cooldown_seconds, cast_time_ms, damage_coefficient) = match raw_skill.grade {
+            SkillGrade::Awakening => (Self::get_random_value(240, 300), 2000, Self::get_random_f64_value(15.0, 20.0)),
+            SkillGrade::Super => (Self::get_random_value(20, 30), 1200, Self::get_random_f64_value(3.0, 4.5)),
+            SkillGrade::Normal | SkillGrade::Unknown => (Self::get_random_value(6, 16), 500, Self::get_random_f64_value(0.8, 2.0)),
+        };
 
-    pub fn to_fight_update_event(&self) -> impl AppEvent {
-        let mut players: Vec<Player> = self.players.iter().map(|player| player.entity.clone()).collect();
-        players.sort_unstable_by(|a, b| a.stats.total_damage.cmp(&b.stats.total_damage));
+        let (stagger, destruction) = match raw_skill.grade {
+            SkillGrade::Awakening => (Self::get_random_value(1500, 2500), 2),
+            SkillGrade::Super => (Self::get_random_value(400, 900), Self::get_random_value(0, 1)),
+            SkillGrade::Normal | SkillGrade::Unknown => (Self::get_random_value(50, 300), 0),
+        };
 
-        let esthers = self.esthers.iter().map(|esther| esther.entity.clone()).collect();
+        SimulatedSkill {
+            skill,
+            grade: raw_skill.grade,
+            cooldown: TimeDelta::seconds(cooldown_seconds),
+            cast_time: TimeDelta::milliseconds(cast_time_ms),
+            damage_coefficient,
+            is_hyper_awakening: false,
+            can_counter: raw_skill.grade == SkillGrade::Normal && Self::get_random_index(8) == 0,
+            stagger,
+            destruction
+        }
+    }
 
-        let app_event = FightUpdate {
-            esthers,
-            stats: self.stats.clone(),
-            boss: self.boss.clone().unwrap(),
-            players
+    fn hyper_awakening_skill() -> SimulatedSkill {
+        let skill = Skill {
+            id: HYPER_AWAKENING_SKILL_ID,
+            name: "Hyper Awakening".into(),
+            icon: "hyper_awakening.png".into()
         };
 
-        app_event
+        SimulatedSkill {
+            skill,
+            grade: SkillGrade::Awakening,
+            cooldown: TimeDelta::seconds(300),
+            cast_time: TimeDelta::milliseconds(3000),
+            damage_coefficient: Self::get_random_f64_value(30.0, 40.0),
+            is_hyper_awakening: true,
+            can_counter: false,
+            stagger: 3000,
+            destruction: 3
+        }
     }
 
-    pub fn generate_random_range(size: u32) -> (u32, u32) {
-        let mut rng = rand::thread_rng();
-        let start = 2;
-        let end = rng.gen_range(start..=size);
+    /// Picks up the names and icons changed since the fight was configured,
+    /// for the skills players cast from now on and for the stats so far.
+    pub fn refresh_names(&mut self, names: &impl NameLookup) {
+        for player in self.players.iter_mut() {
+            for simulated_skill in player.template.skills.iter_mut() {
+                if let Some(skill) = names.skill(simulated_skill.skill.id) {
+                    simulated_skill.skill = skill;
+                }
+            }
+        }
 
-        (start, end)
+        self.aggregator.refresh_names(names);
     }
 
-    pub fn get_random_alive_player(players: &mut Vec<PlayerWithTemplate>) -> &mut Player {
-        let players_count = players.len();
-        let mut player_index = Self::get_random_index(players_count);
-        let player = players.get_mut(player_index).unwrap();
+    pub fn to_fight_update(&self) -> Option<FightUpdate> {
+        self.aggregator.to_fight_update()
+    }
 
-        while player.entity.death_log.is_dead {
-            player_index = Self::get_random_index(players_count);
-        }
+    pub fn to_fight_update_event(&self) -> impl AppEvent {
+        self.aggregator.to_fight_update().unwrap()
+    }
+
+    pub fn to_encounter(&self) -> Option<Encounter> {
+        self.aggregator.to_encounter()
+    }
 
-        let player = players.get_mut(player_index).unwrap();
+    /// `None` once every player is dead.
+    fn get_random_alive_player_id(&self) -> Option<EntityId> {
+        let alive_player_ids: Vec<EntityId> = self.aggregator.players()
+            .filter(|player| !player.death_log.is_dead)
+            .map(|player| player.id)
+            .collect();
 
-        &mut player.entity
+        alive_player_ids.choose(&mut rand::thread_rng()).copied()
     }
-    
 
     fn get_random_esther(esthers: &mut Vec<EstherWithTemplate>) -> &mut EstherWithTemplate {
         let mut rng = rand::thread_rng();
@@ -542,14 +852,6 @@ impl<'a> FightSimulator<'a> {
         &mut esthers[random_index]
     }
 
-    fn get_random_item<T>(items: &[T]) -> &T {
-        debug!("random_item");
-        let mut rng = rand::thread_rng();
-        let random_index = rng.gen_range(0..items.len());
-    
-        &items[random_index]
-    }
-
     fn get_random_index(size: usize) -> usize {
         debug!("random_index");
         let mut rng = rand::thread_rng();
@@ -568,6 +870,11 @@ impl<'a> FightSimulator<'a> {
         rng.gen_range(min..=max)
     }
 
+    fn get_random_f64_value(min: f64, max: f64) -> f64 {
+        let mut rng = rand::thread_rng();
+        rng.gen_range(min..=max)
+    }
+
     fn get_random_nickname(&self, length: usize) -> String {
         let mut rng = rand::thread_rng();
         let mut result = String::new();
@@ -629,4 +936,79 @@ impl<'a> FightSimulator<'a> {
     
         self.sup_classes[random_index]
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulated_skill(id: SkillId, cooldown_seconds: i64, cast_time_ms: i64) -> SimulatedSkill {
+        SimulatedSkill {
+            skill: Skill {
+                id,
+                name: id.to_string(),
+                icon: "".into()
+            },
+            grade: SkillGrade::Normal,
+            cooldown: TimeDelta::seconds(cooldown_seconds),
+            cast_time: TimeDelta::milliseconds(cast_time_ms),
+            damage_coefficient: 1.0,
+            is_hyper_awakening: false,
+            can_counter: false,
+            stagger: 0,
+            destruction: 0
+        }
+    }
+
+    #[test]
+    fn test_rotation_follows_priority_and_cooldowns() {
+        let now = Utc::now();
+        let skills = vec![simulated_skill(1, 10, 500), simulated_skill(2, 5, 500)];
+        let mut rotation = RotationState::new(skills.len(), now);
+
+        assert_eq!(rotation.next_skill_index(now), Some(0));
+        rotation.start_cast(0, &skills[0], now);
+
+        assert_eq!(rotation.next_skill_index(now), None);
+
+        let now = now + TimeDelta::milliseconds(500);
+        assert_eq!(rotation.next_skill_index(now), Some(1));
+        rotation.start_cast(1, &skills[1], now);
+
+        let now = now + TimeDelta::seconds(1);
+        assert_eq!(rotation.next_skill_index(now), None);
+
+        let now = now + TimeDelta::seconds(9);
+        assert_eq!(rotation.next_skill_index(now), Some(0));
+    }
+
+    #[test]
+    fn test_tick_skips_the_boss_attack_once_the_party_is_dead() {
+        let now = Utc::now();
+        let mut fight_simulator = FightSimulator::new();
+        fight_simulator.create_8_players(1_000, 2_000).unwrap();
+        fight_simulator.create_boss(485000, 100_000_000_000).unwrap();
+        fight_simulator.esther_gauge = 3.0;
+
+        for player in fight_simulator.players.iter() {
+            fight_simulator.aggregator.process(EncounterEvent::Death(DeathEvent {
+                target_id: player.id,
+                recorded_on: now
+            }));
+        }
+
+        assert!(fight_simulator.get_random_alive_player_id().is_none());
+
+        fight_simulator.tick(now).unwrap();
+
+        assert!(fight_simulator.aggregator.players().all(|player| player.stats.damage_taken == 0));
+        assert!(fight_simulator.esther_gauge >= 3.0);
+    }
+
+    #[test]
+    fn test_made_up_skill_ids_are_not_in_the_dataset() {
+        let ids = [HYPER_AWAKENING_SKILL_ID, BOSS_ATTACK_SKILL_ID];
+
+        assert_ne!(ids[0], ids[1]);
+        assert!(ids.iter().all(|id| !SKILL_MAP.contains_key(id)));
+    }
+}
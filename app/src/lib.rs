@@ -5,9 +5,13 @@ mod abstractions;
 mod mocks;
 mod commands;
 mod system_tray;
-mod fight_simulator;
+pub mod fight_simulator;
+pub mod scenario;
 mod background;
 mod misc;
+mod coalescing_emitter;
+mod data_overrides;
+mod settings;
 
 use log::error;
 use tauri::{generate_context, Context};
@@ -1,25 +1,22 @@
 use std::error::Error;
 
-use app_core::models::*;
-use log::debug;
-use serde::Serialize;
+use app_core::{codec::{self, PayloadEncoding}, models::*};
 use tauri::{AppHandle, Emitter, EventTarget};
 
 pub trait AppEventEmitter : Send + Sync + 'static  {
-    fn emit(&self, event: impl AppEvent) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn emit(&self, event: impl AppEvent + Send + 'static) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
 
 pub struct DefaultEventEmitter {
     app_handle: AppHandle,
-    target: EventTarget
+    target: EventTarget,
+    encoding: PayloadEncoding
 }
 
 impl AppEventEmitter for DefaultEventEmitter {
-    fn emit(&self, event: impl AppEvent) -> Result<(), Box<dyn Error + Send + Sync>> {
-
-        let json_string = serde_json::to_string_pretty(&event)?;
-        debug!("{json_string}");
-        self.app_handle.emit_to(self.target.clone(), event.event_name(), event)?;
+    fn emit(&self, event: impl AppEvent + Send + 'static) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let payload = codec::encode(&event, self.encoding)?;
+        self.app_handle.emit_to(self.target.clone(), event.event_name(), payload)?;
         Ok(())
     }
 }
@@ -27,10 +24,12 @@ impl AppEventEmitter for DefaultEventEmitter {
 impl DefaultEventEmitter {
     pub fn new(
         app_handle: AppHandle,
-        target: EventTarget) -> Self {
+        target: EventTarget,
+        encoding: PayloadEncoding) -> Self {
         Self {
             app_handle,
-            target
+            target,
+            encoding
         }
     }
 }
\ No newline at end of file
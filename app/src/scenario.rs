@@ -0,0 +1,68 @@
+use anyhow::Error;
+
+use crate::fight_simulator::{BossSkillTemplate, EstherTemplate, FightSimulator, SupportBuffTemplate};
+
+pub fn create_default_scenario() -> Result<FightSimulator<'static>, Error> {
+    let mut fight_simulator = FightSimulator::new();
+    let min_dmg = 100_000_000;
+    let max_dmg = 250_000_000;
+    fight_simulator.create_8_players(min_dmg, max_dmg)?;
+    fight_simulator.set_random_player_dead();
+    fight_simulator.create_boss(485000, 100_000_000_000)?;
+    fight_simulator.configure_boss_skill(BossSkillTemplate {
+        id: 4850001,
+        name: "Crimson Slash".into(),
+        icon: "".into(),
+        min_dmg: 10_000,
+        max_dmg: 20_000
+    });
+    fight_simulator.configure_boss_skill(BossSkillTemplate {
+        id: 4850002,
+        name: "Doom Pillar".into(),
+        icon: "".into(),
+        min_dmg: 30_000,
+        max_dmg: 60_000
+    });
+    fight_simulator.configure_boss_skill(BossSkillTemplate {
+        id: 4850003,
+        name: "Red Shockwave".into(),
+        icon: "".into(),
+        min_dmg: 80_000,
+        max_dmg: 120_000
+    });
+    fight_simulator.configure_support_buff(SupportBuffTemplate {
+        status_effect_id: 210230,
+        duration_seconds: 10,
+        cooldown_seconds: 12
+    })?;
+    fight_simulator.configure_support_buff(SupportBuffTemplate {
+        status_effect_id: 211400,
+        duration_seconds: 8,
+        cooldown_seconds: 15
+    })?;
+    fight_simulator.configure_support_buff(SupportBuffTemplate {
+        status_effect_id: 211410,
+        duration_seconds: 6,
+        cooldown_seconds: 20
+    })?;
+    fight_simulator.configure_esther(EstherTemplate {
+        name: "Azena".into(),
+        icon: "azena.png".into(),
+        min_dmg: 2_000_000_000,
+        max_dmg: 4_000_000_000
+    });
+    fight_simulator.configure_esther(EstherTemplate {
+        name: "Avele".into(),
+        icon: "avele.png".into(),
+        min_dmg: 1_000_000_000,
+        max_dmg: 2_000_000_000
+    });
+    fight_simulator.configure_esther(EstherTemplate {
+        name: "Thar".into(),
+        icon: "thar.png".into(),
+        min_dmg: 500_000_000,
+        max_dmg: 1_000_000_000
+    });
+
+    Ok(fight_simulator)
+}
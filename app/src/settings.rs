@@ -0,0 +1,25 @@
+use std::{fs, path::Path};
+
+use app_core::settings::Settings;
+use data::json::DEFAULT_SETTINGS;
+use log::warn;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Settings saved in `app_data_dir`, the default settings when there are none or they can not be read.
+pub fn load_settings(app_data_dir: &Path) -> Settings {
+    let path = app_data_dir.join(SETTINGS_FILE);
+
+    if !path.is_file() {
+        return DEFAULT_SETTINGS.clone();
+    }
+
+    let settings = fs::read(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|err| err.to_string()));
+
+    settings.unwrap_or_else(|err| {
+        warn!("could not load {}, using the default settings: {}", path.display(), err);
+        DEFAULT_SETTINGS.clone()
+    })
+}
@@ -3,7 +3,7 @@ use log::{error, warn};
 use tauri::{App, EventTarget, Manager};
 use tokio::task;
 
-use crate::{background::run_background_work, misc::DefaultEventEmitter, system_tray::setup_system_tray};
+use crate::{background::{run_background_work, FightUpdateSubscription, NameRefresh}, coalescing_emitter::{CoalescingEventEmitter, CoalescingOptions}, data_overrides::watch_data_overrides, misc::DefaultEventEmitter, settings::load_settings, system_tray::setup_system_tray};
 
 pub fn setup_app(app: &mut App) -> Result<(), Box<dyn Error>> {
 
@@ -16,15 +16,29 @@ pub fn setup_app(app: &mut App) -> Result<(), Box<dyn Error>> {
     let app_handle = app.app_handle();
     setup_system_tray(app)?;
 
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    let settings = load_settings(&app_data_dir);
+    app.manage(settings.clone());
+
     let event_emitter = DefaultEventEmitter::new(
         app_handle.clone(),
-        EventTarget::WebviewWindow { label: "main".into() });
-    let shared_event_emitter: Arc<DefaultEventEmitter> = Arc::new(event_emitter);
+        EventTarget::WebviewWindow { label: "main".into() },
+        settings.general.payload_encoding);
+    let coalescing_options = CoalescingOptions::from_frame_rate(settings.general.frame_rate);
+    let shared_event_emitter = Arc::new(CoalescingEventEmitter::new(event_emitter, coalescing_options));
+    tokio::spawn(shared_event_emitter.clone().run());
+    let subscription = Arc::new(FightUpdateSubscription::default());
+    app.manage(subscription.clone());
+    let name_refresh = Arc::new(NameRefresh::default());
+    app.manage(name_refresh.clone());
+
+    let overrides_dir = app_data_dir.join("overrides");
+    tokio::spawn(watch_data_overrides(overrides_dir, shared_event_emitter.clone(), name_refresh.clone()));
 
     tokio::spawn(async move {
         let event_emitter = shared_event_emitter.clone();
        
-        let result = run_background_work(event_emitter).await;
+        let result = run_background_work(event_emitter, subscription, name_refresh).await;
 
         match result {
             Ok(_) => {
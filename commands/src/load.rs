@@ -1,16 +1,23 @@
+use app_core::settings::Settings;
 use serde::Serialize;
 
 
 #[derive(Serialize)]
 pub struct LoadResult {
-    pub version: String
+    pub version: String,
+    /// Locale the names are shown in, `en` when there are none for the requested locale.
+    pub locale: String,
+    /// Settings the app was started with.
+    pub settings: Settings
 }
 
 #[inline(always)]
-pub fn load_inner(version: String) -> LoadResult {
+pub fn load_inner(version: String, locale: String, settings: Settings) -> LoadResult {
 
 
     LoadResult {
-        version
+        version,
+        locale,
+        settings
     }
 }
\ No newline at end of file
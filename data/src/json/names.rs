@@ -0,0 +1,167 @@
+//! Localized names from the name tables generated out of `source/names/`, the datasets hold the English ones.
+
+use std::sync::RwLock;
+
+use app_core::{models::{ClassId, NpcId, Skill, SkillId, StatusEffectId}, names::NameLookup};
+
+use super::{find_class, find_npc, find_skill, find_status_effect, StaticMap, NAME_TABLES};
+
+/// Locale of the names in the datasets, used when there is no table for the requested locale.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Names of one locale by id, entries missing from it fall back to the English ones.
+#[derive(Debug)]
+pub struct NameTable {
+    pub locale: &'static str,
+    pub classes: StaticMap<&'static str>,
+    pub skills: StaticMap<&'static str>,
+    pub npcs: StaticMap<&'static str>,
+    pub status_effects: StaticMap<&'static str>
+}
+
+static NAME_TABLE: RwLock<Option<&'static NameTable>> = RwLock::new(None);
+
+/// Shows names in `locale`, e.g. `ko`, `ko-KR` or `ko_KR`, a region without a table of its own uses the one of
+/// its language and a locale without any the English names. Returns the locale in effect.
+pub fn set_locale(locale: &str) -> &'static str {
+    let name_table = find_name_table(NAME_TABLES, locale);
+
+    *NAME_TABLE.write().unwrap() = name_table;
+
+    name_table.map_or(DEFAULT_LOCALE, |name_table| name_table.locale)
+}
+
+/// Name table of the locale set last, `None` for English.
+pub fn current_name_table() -> Option<&'static NameTable> {
+    *NAME_TABLE.read().unwrap()
+}
+
+fn find_name_table(name_tables: &'static [NameTable], locale: &str) -> Option<&'static NameTable> {
+    let locale = locale.replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default();
+
+    name_tables.iter()
+        .find(|name_table| name_table.locale.eq_ignore_ascii_case(&locale))
+        .or_else(|| name_tables.iter().find(|name_table| name_table.locale.eq_ignore_ascii_case(language)))
+}
+
+/// [`NameLookup`] over the datasets with the overrides merged over them,
+/// in the locale of `name_table` for the names it has and in English for the others.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DataNames {
+    name_table: Option<&'static NameTable>
+}
+
+impl DataNames {
+    /// Names in the locale set last.
+    pub fn current() -> Self {
+        Self {
+            name_table: current_name_table()
+        }
+    }
+
+    fn localized(&self, names: impl Fn(&NameTable) -> &StaticMap<&'static str>, id: u32) -> Option<String> {
+        self.name_table.and_then(|name_table| names(name_table).get(&id)).map(|name| name.to_string())
+    }
+}
+
+impl NameLookup for DataNames {
+    fn class_name(&self, class_id: ClassId) -> Option<String> {
+        self.localized(|name_table| &name_table.classes, class_id)
+            .or_else(|| find_class(class_id).map(|class| class.name.into()))
+    }
+
+    fn skill(&self, skill_id: SkillId) -> Option<Skill> {
+        let skill = find_skill(skill_id)?;
+        let name = self.localized(|name_table| &name_table.skills, skill_id).or_else(|| skill.name.map(Into::into))?;
+
+        Some(Skill {
+            id: skill.id,
+            name,
+            icon: skill.icon.unwrap_or_default().into()
+        })
+    }
+
+    fn npc_name(&self, npc_id: NpcId) -> Option<String> {
+        self.localized(|name_table| &name_table.npcs, npc_id)
+            .or_else(|| find_npc(npc_id).map(|npc| npc.name.into()))
+    }
+
+    fn status_effect_name(&self, status_effect_id: StatusEffectId) -> Option<String> {
+        self.localized(|name_table| &name_table.status_effects, status_effect_id)
+            .or_else(|| find_status_effect(status_effect_id).map(|status_effect| status_effect.name.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static NAME_TABLES: &[NameTable] = &[
+        NameTable {
+            locale: "ko",
+            classes: StaticMap::new(&[(102, "버서커")]),
+            skills: StaticMap::new(&[(102001, "피니쉬 스트라이크")]),
+            npcs: StaticMap::new(&[]),
+            status_effects: StaticMap::new(&[])
+        },
+        NameTable {
+            locale: "pt",
+            classes: StaticMap::new(&[]),
+            skills: StaticMap::new(&[]),
+            npcs: StaticMap::new(&[]),
+            status_effects: StaticMap::new(&[])
+        },
+    ];
+
+    fn locale_of(locale: &str) -> Option<&'static str> {
+        find_name_table(NAME_TABLES, locale).map(|name_table| name_table.locale)
+    }
+
+    #[test]
+    fn test_locale_falls_back_to_its_language_then_english() {
+        assert_eq!(locale_of("ko"), Some("ko"));
+        assert_eq!(locale_of("ko-KR"), Some("ko"));
+        assert_eq!(locale_of("pt_BR"), Some("pt"));
+        assert_eq!(locale_of("fr-FR"), None);
+        assert_eq!(locale_of(""), None);
+
+        assert_eq!(set_locale("ko-KR"), "ko");
+        assert_eq!(set_locale("xx"), DEFAULT_LOCALE);
+        assert!(current_name_table().is_none());
+    }
+
+    #[test]
+    fn test_missing_names_fall_back_to_english() {
+        let names = DataNames {
+            name_table: Some(&NAME_TABLES[0])
+        };
+
+        assert_eq!(names.class_name(102).as_deref(), Some("버서커"));
+        assert_eq!(names.class_name(103).as_deref(), Some("Destroyer"));
+        assert_eq!(names.npc_name(485000).as_deref(), Some("Red Doom Narkiel"));
+        assert_eq!(names.status_effect_name(210230).as_deref(), Some("Brand"));
+        assert!(names.class_name(1).is_none());
+    }
+
+    #[test]
+    fn test_localized_skill_keeps_the_dataset_icon() {
+        let names = DataNames {
+            name_table: Some(&NAME_TABLES[0])
+        };
+
+        assert_eq!(names.skill(102001).unwrap().name, "피니쉬 스트라이크");
+        assert_eq!(names.skill(102001).unwrap().icon, find_skill(102001).unwrap().icon.unwrap());
+        assert_eq!(names.skill(102002).unwrap().name, find_skill(102002).unwrap().name.unwrap());
+    }
+
+    #[test]
+    fn test_shipped_korean_names_cover_the_boss() {
+        let names = DataNames {
+            name_table: find_name_table(super::NAME_TABLES, "ko")
+        };
+
+        assert_eq!(names.npc_name(485000).as_deref(), Some("붉은 파멸의 나르키엘"));
+        assert!(names.name_table.unwrap().skills.is_empty());
+    }
+}
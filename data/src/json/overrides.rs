@@ -0,0 +1,147 @@
+//! Override dumps loaded at runtime and merged over the embedded datasets, so names, icons and
+//! entries added by a game patch do not need a new release.
+
+use std::{fs, path::Path, sync::{Arc, RwLock}};
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+
+use super::models::*;
+
+/// Override dumps in the format of the source dumps, each optional and holding only the entries to add or replace.
+pub const OVERRIDE_FILES: [&str; 4] = ["Class.json", "Skill.json", "Npc.json", "StatusEffect.json"];
+
+/// Entries of the override dumps by id, looked up before the embedded datasets.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub classes: FxHashMap<u32, Class<'static>>,
+    pub skills: FxHashMap<u32, RawSkill<'static>>,
+    pub npcs: FxHashMap<u32, Npc<'static>>,
+    pub status_effects: FxHashMap<u32, StatusEffect<'static>>
+}
+
+static OVERRIDES: Lazy<RwLock<Arc<Overrides>>> = Lazy::new(Default::default);
+
+/// Overrides loaded last, empty until [`load_overrides`] succeeds.
+pub fn current_overrides() -> Arc<Overrides> {
+    OVERRIDES.read().unwrap().clone()
+}
+
+/// Loads the override dumps found in `dir` and makes them current, a missing file overrides nothing.
+/// On error the overrides loaded before stay current.
+pub fn load_overrides(dir: &Path) -> Result<Arc<Overrides>> {
+    let overrides = Arc::new(Overrides {
+        classes: read_overrides(dir, "Class.json", |class: &Class| class.id)?,
+        skills: read_overrides(dir, "Skill.json", |skill: &RawSkill| skill.id)?,
+        npcs: read_overrides(dir, "Npc.json", |npc: &Npc| npc.id)?,
+        status_effects: read_overrides(dir, "StatusEffect.json", |status_effect: &StatusEffect| status_effect.id)?
+    });
+
+    *OVERRIDES.write().unwrap() = overrides.clone();
+
+    Ok(overrides)
+}
+
+/// Entries own their strings, unlike the embedded ones which borrow them from the binary.
+fn read_overrides<T: DeserializeOwned>(dir: &Path, name: &str, id: impl Fn(&T) -> u32) -> Result<FxHashMap<u32, T>> {
+    let path = dir.join(name);
+
+    if !path.is_file() {
+        return Ok(FxHashMap::default());
+    }
+
+    let contents = fs::read(&path).with_context(|| format!("could not read {}", path.display()))?;
+    let entries: FxHashMap<u32, T> = serde_json::from_slice(&contents)
+        .with_context(|| format!("could not parse {}", path.display()))?;
+
+    for (key, entry) in &entries {
+        if id(entry) != *key {
+            bail!("{}: entry {key} has id {}", path.display(), id(entry));
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process, sync::{Mutex, MutexGuard}};
+
+    use super::*;
+    use crate::json::{find_class, find_skill};
+
+    static CURRENT_OVERRIDES: Mutex<()> = Mutex::new(());
+
+    /// Holds on to the current overrides for the test, other tests of the crate only look up entries these leave alone.
+    fn lock() -> MutexGuard<'static, ()> {
+        CURRENT_OVERRIDES.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Empty directory of its own for each test.
+    fn overrides_dir(test_name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("data-overrides-{}-{test_name}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const SKILL_OVERRIDE: &str = r#"{
+        "999001": { "id": 999001, "name": "Patched Skill", "desc": "Line1\nLine2 \"quoted\" \u00e9", "classid": 102, "icon": "patched.png", "grade": "normal" }
+    }"#;
+
+    #[test]
+    fn test_overrides_take_precedence_over_embedded_entries() {
+        let _lock = lock();
+        let dir = overrides_dir("precedence");
+        fs::write(dir.join("Class.json"), r#"{ "101": { "id": 101, "name": "Warrior", "isGeneric": true, "isSupport": false } }"#).unwrap();
+        fs::write(dir.join("Skill.json"), SKILL_OVERRIDE).unwrap();
+
+        load_overrides(&dir).unwrap();
+
+        assert_eq!(find_class(101).unwrap().name, "Warrior");
+        let skill = find_skill(999001).unwrap();
+        assert_eq!(skill.name.as_deref(), Some("Patched Skill"));
+        assert_eq!(skill.desc.as_deref(), Some("Line1\nLine2 \"quoted\" \u{e9}"));
+        assert_eq!(find_class(102).unwrap().name, "Berserker");
+    }
+
+    #[test]
+    fn test_mismatched_key_and_id_are_rejected() {
+        let _lock = lock();
+        let dir = overrides_dir("mismatch");
+        fs::write(dir.join("Npc.json"), r#"{ "1": { "id": 2, "name": "Boss", "raid": "Raid", "gate": 1, "difficulty": "normal", "hpBars": 1, "imageKey": "boss" } }"#).unwrap();
+
+        let err = load_overrides(&dir).unwrap_err();
+
+        assert!(err.to_string().ends_with("Npc.json: entry 1 has id 2"), "{err}");
+    }
+
+    #[test]
+    fn test_removed_file_drops_its_overrides() {
+        let _lock = lock();
+        let dir = overrides_dir("removed");
+        fs::write(dir.join("Skill.json"), SKILL_OVERRIDE).unwrap();
+        assert_eq!(load_overrides(&dir).unwrap().skills.len(), 1);
+
+        fs::remove_file(dir.join("Skill.json")).unwrap();
+
+        assert!(load_overrides(&dir).unwrap().skills.is_empty());
+    }
+
+    #[test]
+    fn test_failed_load_keeps_previous_overrides() {
+        let _lock = lock();
+        let dir = overrides_dir("failed");
+        fs::write(dir.join("StatusEffect.json"), r#"{
+            "999002": { "id": 999002, "name": "Patched Buff", "icon": null, "category": "buff", "sourceClassId": 0, "isPartySynergy": false }
+        }"#).unwrap();
+        let loaded = load_overrides(&dir).unwrap();
+
+        fs::write(dir.join("StatusEffect.json"), "{ not json").unwrap();
+
+        assert!(load_overrides(&dir).is_err());
+        assert!(Arc::ptr_eq(&current_overrides(), &loaded));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod json;
+pub mod static_map;
 mod deserializer;
\ No newline at end of file
@@ -0,0 +1,45 @@
+/// Read only map embedded in the binary, generated by `build.rs` with its entries sorted by key
+/// so lookups are a binary search and nothing is parsed or allocated at runtime.
+#[derive(Debug)]
+pub struct StaticMap<V: 'static> {
+    entries: &'static [(u32, V)]
+}
+
+impl<V> StaticMap<V> {
+    pub const fn new(entries: &'static [(u32, V)]) -> Self {
+        Self {
+            entries
+        }
+    }
+
+    pub fn get(&self, key: &u32) -> Option<&V> {
+        self.entries
+            .binary_search_by_key(key, |(key, _)| *key)
+            .ok()
+            .map(|index| &self.entries[index].1)
+    }
+
+    pub fn contains_key(&self, key: &u32) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&u32, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &u32> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
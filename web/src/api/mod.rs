@@ -1,3 +1,4 @@
+use app_core::settings::Settings;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
@@ -11,6 +12,9 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     async fn listen(cmd: &str, args: JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
 }
 
 #[derive(Serialize, Deserialize)]
@@ -20,17 +24,28 @@ pub struct LoadArgs<'a> {
 
 #[derive(Serialize, Deserialize)]
 pub struct LoadResult {
-    pub version: String
+    pub version: String,
+    /// Locale the backend shows names in, `en` when it has none for the requested one.
+    pub locale: String,
+    pub settings: Settings
 }
 
-pub async fn load<'a>() -> Result<LoadResult, serde_wasm_bindgen::Error> {
-    let args = LoadArgs { 
-        locale: "" 
+/// Loads the app with the names in the browser language.
+pub async fn load() -> Result<LoadResult, JsValue> {
+    let locale = web_sys::window()
+        .and_then(|window| window.navigator().language())
+        .unwrap_or_default();
+    let args = LoadArgs {
+        locale: &locale
     };
-    // let args = serde_wasm_bindgen::to_value(&args).unwrap();
-    // let value = invoke("load", args).await;
-    // serde_wasm_bindgen::from_value::<LoadResult>(value)
-    Ok(LoadResult {
-        version: "0.0.1".into()
-    })
-}
\ No newline at end of file
+    let args = serde_wasm_bindgen::to_value(&args)?;
+    let value = try_invoke("load", args).await?;
+
+    Ok(serde_wasm_bindgen::from_value::<LoadResult>(value)?)
+}
+
+/// Asks the backend to send the next fight update as a full snapshot.
+pub async fn subscribe_fight_updates() -> Result<(), JsValue> {
+    try_invoke("subscribe_fight_updates", JsValue::NULL).await?;
+    Ok(())
+}
@@ -1,13 +1,16 @@
-use log::info;
+use app_core::settings::FormatSettings;
+use log::{error, info};
 use yew::{platform::spawn_local, prelude::*};
 
 use crate::api::load;
 
 
 #[derive(Clone, Debug, PartialEq)]
-struct AppState {
+pub struct AppState {
     is_loading: bool,
     version: String,
+    /// Format settings of the backend, the defaults until it has loaded.
+    pub format: FormatSettings,
 }
 
 #[derive(Properties, Debug, PartialEq)]
@@ -21,6 +24,7 @@ pub fn provider(props: &Props) -> Html {
     let context = use_state(|| AppState {
         is_loading: true,
         version: "".to_owned(),
+        format: FormatSettings::default(),
     });
 
     {
@@ -33,10 +37,22 @@ pub fn provider(props: &Props) -> Html {
                 }
               
                 info!("Loading...");
-                let load_result = load().await.unwrap();
-                let app_state = AppState {
-                    is_loading: false,
-                    version: load_result.version
+                let app_state = match load().await {
+                    Ok(load_result) => {
+                        info!("names shown in {}", load_result.locale);
+                        AppState {
+                            is_loading: false,
+                            version: load_result.version,
+                            format: load_result.settings.format
+                        }
+                    },
+                    Err(err) => {
+                        error!("could not load: {:?}", err);
+                        AppState {
+                            is_loading: false,
+                            ..(*context).clone()
+                        }
+                    },
                 };
                 context.set(app_state);
             });
@@ -0,0 +1,126 @@
+use app_core::settings::{FormatSettings, TimeFormat};
+use chrono::Duration;
+
+const UNITS: [(f64, &str); 4] = [(1e3, "k"), (1e6, "m"), (1e9, "b"), (1e12, "t")];
+
+/// Turns the raw numbers and durations of the models into display strings per [`FormatSettings`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formatter {
+    settings: FormatSettings
+}
+
+impl Formatter {
+    pub fn new(settings: FormatSettings) -> Self {
+        Self {
+            settings
+        }
+    }
+
+    /// Value and unit suffix shown separately, e.g. `("1.2", "m")`.
+    pub fn number_parts(&self, value: i64) -> (String, &'static str) {
+        if !self.settings.abbreviate_numbers {
+            return (value.to_string(), "");
+        }
+
+        let (value, unit) = Self::abbreviate(value, self.settings.decimals);
+
+        if unit.is_empty() {
+            return (format!("{value}"), unit);
+        }
+
+        (format!("{:.*}", self.settings.decimals, value), unit)
+    }
+
+    /// Percentage of a fraction without the `%` sign.
+    pub fn percentage(&self, fraction: f32) -> String {
+        format!("{:.*}", self.settings.percentage_decimals, fraction * 100.0)
+    }
+
+    pub fn duration(&self, duration: Duration) -> String {
+        let seconds = duration.num_seconds();
+
+        match self.settings.time_format {
+            TimeFormat::MinutesSeconds => Self::minutes_seconds(seconds),
+            TimeFormat::Seconds => format!("{seconds}s"),
+        }
+    }
+
+    /// Smallest unit the value shows below 1000 in once rounded to `decimals`, so `999_999` becomes `1.0m`
+    /// rather than `1000.0k`. Values below a thousand are left as they are.
+    fn abbreviate(value: i64, decimals: usize) -> (f64, &'static str) {
+        let value = value as f64;
+
+        if value < 1e3 {
+            return (value, "");
+        }
+
+        let rounding = 10f64.powi(decimals as i32);
+        let (unit_value, unit) = UNITS.iter()
+            .find(|(unit_value, _)| (value / unit_value * rounding).round() < 1e3 * rounding)
+            .unwrap_or(&UNITS[UNITS.len() - 1]);
+
+        (value / unit_value, unit)
+    }
+
+    /// Negative durations show as `00:00`.
+    fn minutes_seconds(seconds: i64) -> String {
+        let seconds = seconds.max(0);
+        let minutes = seconds / 60;
+
+        if minutes >= 60 {
+            return "∞".into();
+        }
+
+        format!("{:02}:{:02}", minutes, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter_with(update: impl FnOnce(&mut FormatSettings)) -> Formatter {
+        let mut settings = FormatSettings::default();
+        update(&mut settings);
+        Formatter::new(settings)
+    }
+
+    #[test]
+    fn test_numbers_abbreviate_at_unit_boundaries() {
+        let formatter = Formatter::new(FormatSettings::default());
+
+        assert_eq!(formatter.number_parts(999), ("999".into(), ""));
+        assert_eq!(formatter.number_parts(1_000), ("1.0".into(), "k"));
+        assert_eq!(formatter.number_parts(999_949), ("999.9".into(), "k"));
+        assert_eq!(formatter.number_parts(999_999), ("1.0".into(), "m"));
+        assert_eq!(formatter.number_parts(1_234_567), ("1.2".into(), "m"));
+        assert_eq!(formatter.number_parts(1_000_000_000), ("1.0".into(), "b"));
+        assert_eq!(formatter.number_parts(1_500_000_000_000), ("1.5".into(), "t"));
+    }
+
+    #[test]
+    fn test_numbers_follow_decimals_and_abbreviation_settings() {
+        assert_eq!(formatter_with(|settings| settings.decimals = 0).number_parts(1_634_567), ("2".into(), "m"));
+        assert_eq!(formatter_with(|settings| settings.decimals = 0).number_parts(999_500), ("1".into(), "m"));
+        assert_eq!(formatter_with(|settings| settings.decimals = 3).number_parts(1_234_567), ("1.235".into(), "m"));
+        assert_eq!(formatter_with(|settings| settings.abbreviate_numbers = false).number_parts(1_234_567), ("1234567".into(), ""));
+    }
+
+    #[test]
+    fn test_percentage_follows_percentage_decimals() {
+        assert_eq!(Formatter::new(FormatSettings::default()).percentage(0.5), "50.00");
+        assert_eq!(formatter_with(|settings| settings.percentage_decimals = 1).percentage(1.0 / 3.0), "33.3");
+        assert_eq!(formatter_with(|settings| settings.percentage_decimals = 0).percentage(1.0), "100");
+    }
+
+    #[test]
+    fn test_minutes_seconds_show_an_hour_or_more_as_infinite() {
+        let formatter = Formatter::new(FormatSettings::default());
+
+        assert_eq!(formatter.duration(Duration::seconds(3599)), "59:59");
+        assert_eq!(formatter.duration(Duration::seconds(3600)), "∞");
+        assert_eq!(formatter.duration(Duration::seconds(65)), "01:05");
+        assert_eq!(formatter.duration(Duration::seconds(-5)), "00:00");
+        assert_eq!(formatter_with(|settings| settings.time_format = TimeFormat::Seconds).duration(Duration::seconds(3600)), "3600s");
+    }
+}
@@ -4,6 +4,7 @@ mod settings;
 mod meter;
 mod logs;
 mod app_context;
+mod format;
 use app::App;
 
 fn main() {
@@ -14,7 +14,9 @@ use yew::prelude::*;
 use web_sys::console;
 use yew_router::prelude::*;
 use futures::StreamExt;
-use app_core::models::*;
+use app_core::{codec::{self, EncodedPayload}, delta::{FightUpdateDecoder, FightUpdateMessage}, models::*};
+
+use crate::{api, app_context::AppState, format::Formatter};
 
 struct State {
     pub players: Vec<Player>
@@ -23,7 +25,7 @@ struct State {
 async fn listen_for_updates(link: Scope<Meter>) {
     
     let event_stream_result = fight_update_example::fake_listen().await;
-    // let event_stream_result = event::listen::<FightUpdate>("fight-update").await;
+    // let event_stream_result = event::listen::<EncodedPayload>("fight-update-message").await;
     let mut event_stream = match event_stream_result {
         Ok(stream) => stream,
         Err(err) => {
@@ -38,19 +40,55 @@ async fn listen_for_updates(link: Scope<Meter>) {
         },
     };
 
+    subscribe().await;
+
     while let Some(event) = event_stream.next().await {
         link.send_message(MeterMessage::Update(event.payload));
     }
 }
 
+/// The backend refreshed the names and icons of the fight after reloading the data overrides,
+/// a snapshot brings them to the meter.
+async fn listen_for_data_reloads() {
+    let mut event_stream = match event::listen::<EncodedPayload>("data-reloaded").await {
+        Ok(stream) => stream,
+        Err(err) => {
+            error!("could not listen for data reloads: {:?}", err);
+            return;
+        },
+    };
+
+    while let Some(event) = event_stream.next().await {
+        match codec::decode::<DataReloaded>(&event.payload) {
+            Ok(data_reloaded) => info!("data overrides reloaded: {:?}", data_reloaded),
+            Err(err) => error!("could not decode data reload: {err}"),
+        }
+
+        subscribe().await;
+    }
+}
+
+/// Requests a snapshot from the app, or from the fixture replay while the meter runs on it.
+async fn subscribe() {
+    fight_update_example::request_snapshot();
+
+    if let Err(err) = api::subscribe_fight_updates().await {
+        error!("could not subscribe to fight updates: {:?}", err);
+    }
+}
+
 pub struct Meter {
+    decoder: FightUpdateDecoder,
+    formatter: Formatter,
+    _app_state_listener: Option<ContextHandle<AppState>>,
     stats: Option<EncounterStats>,
     players: Vec<Player>,
     boss: Option<Boss>
 }
 
 pub enum MeterMessage {
-    Update(FightUpdate),
+    Update(EncodedPayload),
+    AppState(AppState),
     Error
 }
 
@@ -64,8 +102,17 @@ impl Component for Meter {
         spawn_local(async move {
             listen_for_updates(link).await;
         });
+        spawn_local(listen_for_data_reloads());
+
+        let (app_state, app_state_listener) = context.link()
+            .context::<AppState>(context.link().callback(MeterMessage::AppState))
+            .unzip();
+        let format = app_state.map(|app_state| app_state.format).unwrap_or_default();
 
         Self {
+            decoder: FightUpdateDecoder::new(),
+            formatter: Formatter::new(format),
+            _app_state_listener: app_state_listener,
             stats: None,
             players: vec![],
             boss: None
@@ -75,10 +122,34 @@ impl Component for Meter {
     fn update(&mut self, _context: &Context<Self>, message: Self::Message) -> bool {      
         match message {
             MeterMessage::Update(payload) => {
-                self.players = payload.players;
-                self.boss = Some(payload.boss);
+                let message: FightUpdateMessage = match codec::decode(&payload) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        error!("could not decode fight update: {err}");
+                        return false;
+                    }
+                };
+
+                let fight_update = match self.decoder.apply(message) {
+                    Ok(fight_update) => fight_update,
+                    Err(err) => {
+                        error!("{err}, requesting a snapshot");
+                        spawn_local(subscribe());
+                        return false;
+                    }
+                };
+
+                self.stats = Some(fight_update.stats.clone());
+                self.players = fight_update.players.clone();
+                self.boss = Some(fight_update.boss.clone());
                 true
             },
+            MeterMessage::AppState(app_state) => {
+                let formatter = Formatter::new(app_state.format);
+                let has_changed = formatter != self.formatter;
+                self.formatter = formatter;
+                has_changed
+            },
             MeterMessage::Error => {
                 false
             },
@@ -88,16 +159,32 @@ impl Component for Meter {
     fn view(&self, _context: &Context<Self>) -> Html {
       
         let player_rows: Vec<Html> = self.players.clone().iter().map(|player| {
-            html! { <PlayerRow key={player.id} player={player.clone()} /> }
+            html! { <PlayerRow key={player.id} player={player.clone()} formatter={self.formatter.clone()} /> }
         }).collect();
 
         if let Some(boss) = &self.boss {
+            let duration = self.stats.as_ref()
+                .map(|stats| self.formatter.duration(stats.duration))
+                .unwrap_or_default();
+            let time_to_kill = self.stats.as_ref()
+                .and_then(|stats| stats.time_to_kill.as_ref())
+                .map(|time_to_kill| format!("TTK {}", self.formatter.duration(time_to_kill.estimate)))
+                .unwrap_or_default();
+            let raid_gate = boss.raid_gate.as_ref()
+                .map(|raid_gate| format!("{} G{}", raid_gate.raid, raid_gate.gate))
+                .unwrap_or_default();
+
             return  html! {
                 <div data-tauri-drag-region="true">
                     <div class="flex">
                         {boss.name.clone()}
+                        <span class="ml-2 text-xs text-gray-300">{raid_gate}</span>
+                        <span class="ml-auto mr-2">{time_to_kill}</span>
+                        <span>{duration}</span>
                     </div>
-                    <img class="absolute w-full brightness-25" src="public/images/bosses/narkiel.png"/>
+                    if let Some(image_key) = &boss.image_key {
+                        <img class="absolute w-full brightness-25" src={format!("public/images/bosses/{image_key}.png")}/>
+                    }
                     <table class="relative w-full table-fixed">
                         <PlayerHeader/>
                         <tbody>
@@ -11,6 +11,9 @@ pub fn player_header() -> Html {
                 <th class="w-[60px]">{"D%"}</th>
                 <th class="w-[60px]">{"Crit"}</th>
                 <th class="w-[60px]">{"Back"}</th>
+                <th class="w-[60px]">{"Cntr"}</th>
+                <th class="w-[60px]">{"Stag"}</th>
+                <th class="w-[60px]">{"Dest"}</th>
             </tr>
         </thead>
     }
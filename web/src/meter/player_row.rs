@@ -1,11 +1,12 @@
 use yew::prelude::*;
 use app_core::models::*;
 
-use crate::meter::dps_bar::DpsBar;
+use crate::{format::Formatter, meter::dps_bar::DpsBar};
 
 #[derive(Properties, Debug, PartialEq)]
 pub struct PlayerRowProps {
     pub player: Player,
+    pub formatter: Formatter,
 }
 
 #[function_component(PlayerRow)]
@@ -16,9 +17,12 @@ pub fn player_row(props: &PlayerRowProps) -> Html {
     let class_name = props.player.class_name.to_string();
     let stats = &props.player.stats;
     let img_src = format!("public/images/classes/{}.png", class_id);
-    let dps_value = format!("{:.1}", stats.dps.value);
-    let crit_rate = format!("{:.2}", stats.crit_rate * 100.0);
-    let damage_percentage = format!("{:.2}", stats.damage_percentage * 100.0);
+    let formatter = &props.formatter;
+    let (dps_value, dps_unit) = formatter.number_parts(stats.dps);
+    let crit_rate = formatter.percentage(stats.crit_rate);
+    let damage_percentage = formatter.percentage(stats.damage_percentage);
+    let back_attacks_percentage = formatter.percentage(stats.back_attacks_damage_percentage);
+    let stagger_percentage = formatter.percentage(stats.stagger_percentage);
 
     html! {
         <>
@@ -28,9 +32,13 @@ pub fn player_row(props: &PlayerRowProps) -> Html {
                 <img class="size-5 inline-block mr-2" src={img_src} alt={class_name}/>
                 <span>{name}</span>
             </td>
-            <td class="px-1 text-center">{dps_value}<span class="text-xs text-gray-300">{stats.dps.unit}</span></td>
+            <td class="px-1 text-center">{dps_value}<span class="text-xs text-gray-300">{dps_unit}</span></td>
             <td class="text-center">{damage_percentage}<span class="text-xs text-gray-300">{"%"}</span></td>
             <td class="text-center">{crit_rate}<span class="text-xs text-gray-300">{"%"}</span></td>
+            <td class="text-center">{back_attacks_percentage}<span class="text-xs text-gray-300">{"%"}</span></td>
+            <td class="text-center">{stats.skills.counter_count}</td>
+            <td class="text-center">{stagger_percentage}<span class="text-xs text-gray-300">{"%"}</span></td>
+            <td class="text-center">{stats.destruction}</td>
         </tr>
         </>
     }